@@ -0,0 +1,37 @@
+//! Benchmark reporting the bytes counted by a `Throughput` to criterion.
+//!
+//! Run with `cargo run --release --example criterion --features criterion`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use tp::{to_criterion_throughput, Throughput};
+
+fn checksum(chunks: &[Vec<u8>], tp: &mut Throughput<Instant>) -> u64 {
+    chunks
+        .iter()
+        .map(|chunk| {
+            tp.report(chunk.len() as u64);
+            chunk.iter().map(|&byte| u64::from(byte)).sum::<u64>()
+        })
+        .sum()
+}
+
+fn bench_checksum(c: &mut Criterion) {
+    let chunks: Vec<Vec<u8>> = (0..64).map(|i| vec![i as u8; 4096]).collect();
+
+    // count the bytes a single iteration processes
+    let mut tp = Throughput::new();
+    checksum(&chunks, &mut tp);
+
+    let mut group = c.benchmark_group("checksum");
+    group.throughput(to_criterion_throughput(tp.count()));
+    group.bench_function("chunks", |b| {
+        b.iter(|| checksum(black_box(&chunks), &mut tp))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_checksum);
+criterion_main!(benches);
@@ -0,0 +1,52 @@
+#![no_std]
+
+use core::convert::TryFrom;
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::time::Duration;
+
+use tp::tp::TimeSource;
+use tp::{EwmaThroughput, Throughput, ValueHistogram};
+
+/// Milliseconds since boot, as advanced by a timer interrupt. 32 bits, as
+/// targets like `thumbv7m-none-eabi` lack 64-bit atomics; they last for
+/// about 49 days.
+pub static TICKS: AtomicU32 = AtomicU32::new(0);
+
+/// Time source backed by [`TICKS`].
+pub struct Tick(u32);
+
+impl TimeSource for Tick {
+    fn now() -> Self {
+        Tick(TICKS.load(Ordering::Relaxed))
+    }
+
+    fn now_minus(d: Duration) -> Self {
+        Tick(
+            TICKS
+                .load(Ordering::Relaxed)
+                .saturating_sub(u32::try_from(d.as_millis()).unwrap_or(u32::MAX)),
+        )
+    }
+
+    fn elapsed(&self) -> Duration {
+        Duration::from_millis(u64::from(
+            TICKS.load(Ordering::Relaxed).saturating_sub(self.0),
+        ))
+    }
+
+    fn duration_since(&self, earlier: &Self) -> Duration {
+        Duration::from_millis(u64::from(self.0.saturating_sub(earlier.0)))
+    }
+}
+
+pub fn rate(tp: &mut Throughput<Tick>) -> Option<f64> {
+    tp.throughput()
+}
+
+pub fn average(ewma: &mut EwmaThroughput<Tick>) -> Option<f64> {
+    ewma.tick()
+}
+
+pub fn buckets(histogram: &ValueHistogram<8>) -> [u64; 8] {
+    histogram.buckets()
+}
@@ -0,0 +1,155 @@
+use core::time::Duration;
+
+use crate::tp::Accumulate;
+
+/// Combines the values reported within a window into the single figure a
+/// [`Throughput`](crate::Throughput) returns, e.g. [`MaxValue`] for the
+/// largest value per window instead of the rate.
+pub trait Aggregator<V> {
+    type Acc: Copy;
+
+    /// Returns the accumulator of an empty window.
+    fn zero() -> Self::Acc;
+    fn fold(acc: Self::Acc, value: V) -> Self::Acc;
+    /// Returns the figure of a window which lasted `elapsed`.
+    fn finish(acc: Self::Acc, elapsed: Duration) -> f64;
+}
+
+/// Sum of the values per second, the rate computed by
+/// [`Throughput`](crate::Throughput) by default.
+#[derive(Debug, Clone, Copy)]
+pub struct SumPerSecond;
+
+impl<V: Accumulate> Aggregator<V> for SumPerSecond {
+    type Acc = V;
+
+    fn zero() -> V {
+        V::zero()
+    }
+
+    fn fold(acc: V, value: V) -> V {
+        acc.accumulate(value)
+    }
+
+    fn finish(acc: V, elapsed: Duration) -> f64 {
+        acc.to_f64() / elapsed.as_secs_f64()
+    }
+}
+
+/// Largest value reported, regardless of the elapsed time.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxValue;
+
+impl<V: Accumulate> Aggregator<V> for MaxValue {
+    type Acc = f64;
+
+    fn zero() -> f64 {
+        f64::NEG_INFINITY
+    }
+
+    fn fold(acc: f64, value: V) -> f64 {
+        acc.max(value.to_f64())
+    }
+
+    fn finish(acc: f64, _elapsed: Duration) -> f64 {
+        acc
+    }
+}
+
+/// Number of reports, regardless of their values.
+#[derive(Debug, Clone, Copy)]
+pub struct Count;
+
+impl<V> Aggregator<V> for Count {
+    type Acc = u64;
+
+    fn zero() -> u64 {
+        0
+    }
+
+    fn fold(acc: u64, _value: V) -> u64 {
+        acc.saturating_add(1)
+    }
+
+    fn finish(acc: u64, _elapsed: Duration) -> f64 {
+        acc as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::time::Duration;
+
+    use super::{Count, MaxValue, SumPerSecond};
+    use crate::testing::ManualClock;
+    use crate::tp::{Throughput, ThroughputSynchronized, TimeSource};
+    use crate::unit::{Items, UnitThroughput};
+
+    const VALUES: [u64; 5] = [3, 9, 1, 4, 3];
+
+    #[test]
+    fn test_sum_per_second() {
+        let clock = ManualClock::now();
+        let mut tp: Throughput<ManualClock, u64, SumPerSecond> = Throughput::new();
+        assert_eq!(tp.peek_throughput(), None);
+
+        for value in VALUES {
+            tp.report(value);
+        }
+        clock.advance(Duration::from_secs(2));
+
+        assert_approx_eq!(tp.throughput().unwrap(), 10.0);
+        assert_eq!(tp.peek_throughput(), None);
+    }
+
+    #[test]
+    fn test_max_value() {
+        let clock = ManualClock::now();
+        let mut tp: Throughput<ManualClock, u64, MaxValue> = Throughput::new();
+        assert_eq!(tp.peek_throughput(), None);
+
+        for value in VALUES {
+            tp.report(value);
+        }
+        clock.advance(Duration::from_secs(2));
+
+        assert_approx_eq!(tp.throughput().unwrap(), 9.0);
+        assert_eq!(tp.peek_throughput(), None);
+    }
+
+    #[test]
+    fn test_count() {
+        let mut tp: Throughput<ManualClock, u64, Count> = Throughput::new();
+        for value in VALUES {
+            tp.report(value);
+        }
+
+        assert_approx_eq!(tp.throughput().unwrap(), 5.0);
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_wrappers() {
+        let clock = ManualClock::now();
+        let mut tp: Throughput<ManualClock, u64, MaxValue> =
+            Throughput::builder().track_peak(true).build();
+        tp.report(3);
+        tp.pause();
+        clock.advance(Duration::from_secs(5));
+        tp.resume();
+        tp.report(9);
+        assert_approx_eq!(tp.throughput().unwrap(), 9.0);
+        assert_approx_eq!(tp.peak().unwrap(), 9.0);
+
+        let tp: ThroughputSynchronized<ManualClock, u64, Count> = ThroughputSynchronized::new();
+        tp.report(3);
+        tp.report(4);
+        assert_approx_eq!(tp.throughput().unwrap(), 2.0);
+
+        let mut tp: UnitThroughput<ManualClock, Items, u64, Count> = UnitThroughput::new();
+        tp.report(7);
+        assert_eq!(tp.to_string(), "1.0 items/s");
+    }
+}
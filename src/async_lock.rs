@@ -0,0 +1,260 @@
+use core::fmt;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::ops::DerefMut;
+use core::option::Option;
+use core::time::Duration;
+
+use crate::aggregate::{Aggregator, SumPerSecond};
+use crate::tp::{
+    Accumulate, OverflowError, Snapshot, Throughput, ThroughputError, ThroughputMarker, TimeSource,
+};
+
+/// Asynchronous mutual exclusion used by [`ThroughputAsyncSynchronized`].
+///
+/// Implementations for `tokio` and `async-std` are provided behind the
+/// `async` and `async-std` features. Other runtimes can plug in their own
+/// mutex by implementing this trait.
+pub trait AsyncLock<X> {
+    type Guard<'a>: DerefMut<Target = X>
+    where
+        Self: 'a;
+
+    fn new(value: X) -> Self;
+
+    fn lock(&self) -> impl Future<Output = Self::Guard<'_>>;
+
+    fn into_inner(self) -> X;
+}
+
+/// Throughput which can be shared between tasks, guarded by the async lock
+/// `L`.
+pub struct ThroughputAsyncSynchronized<
+    T: TimeSource,
+    L: AsyncLock<Throughput<T, V, A>>,
+    V: Accumulate = u64,
+    A: Aggregator<V> = SumPerSecond,
+> {
+    tp_unsynchronized: L,
+    _marker: ThroughputMarker<T, V, A>,
+}
+
+impl<
+        T: TimeSource,
+        L: AsyncLock<Throughput<T, V, A>> + fmt::Debug,
+        V: Accumulate,
+        A: Aggregator<V>,
+    > fmt::Debug for ThroughputAsyncSynchronized<T, L, V, A>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThroughputAsyncSynchronized")
+            .field("tp_unsynchronized", &self.tp_unsynchronized)
+            .finish()
+    }
+}
+
+impl<T: TimeSource, L: AsyncLock<Throughput<T, V, A>>, V: Accumulate, A: Aggregator<V>>
+    ThroughputAsyncSynchronized<T, L, V, A>
+{
+    pub fn new() -> Self {
+        Self {
+            tp_unsynchronized: L::new(Throughput::new()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Consumes the wrapper and returns the throughput.
+    pub fn into_inner(self) -> Throughput<T, V, A> {
+        self.tp_unsynchronized.into_inner()
+    }
+
+    pub async fn report(&self, value: V) {
+        self.tp_unsynchronized.lock().await.report(value);
+    }
+
+    pub async fn report_with_latency(&self, value: V, latency: Duration) {
+        self.tp_unsynchronized
+            .lock()
+            .await
+            .report_with_latency(value, latency);
+    }
+
+    /// Reports all `values` while taking the lock only once.
+    pub async fn report_many(&self, values: &[V]) {
+        self.tp_unsynchronized.lock().await.report_many(values);
+    }
+
+    pub async fn report_iter<I: IntoIterator<Item = V>>(&self, values: I) {
+        self.tp_unsynchronized.lock().await.report_iter(values);
+    }
+
+    pub async fn checked_report(&self, value: V) -> Result<(), OverflowError> {
+        self.tp_unsynchronized.lock().await.checked_report(value)
+    }
+
+    pub async fn report_instant(&self, value: V) -> Option<f64> {
+        self.tp_unsynchronized.lock().await.report_instant(value)
+    }
+
+    pub async fn reset(&self) {
+        self.tp_unsynchronized.lock().await.reset();
+    }
+
+    pub async fn rebase_window(&self) {
+        self.tp_unsynchronized.lock().await.rebase_window();
+    }
+
+    pub async fn clear_count(&self) {
+        self.tp_unsynchronized.lock().await.clear_count();
+    }
+
+    pub async fn pause(&self) {
+        self.tp_unsynchronized.lock().await.pause();
+    }
+
+    pub async fn resume(&self) {
+        self.tp_unsynchronized.lock().await.resume();
+    }
+
+    pub async fn is_paused(&self) -> bool {
+        self.tp_unsynchronized.lock().await.is_paused()
+    }
+
+    pub async fn count(&self) -> V {
+        self.tp_unsynchronized.lock().await.count()
+    }
+
+    pub async fn total(&self) -> V {
+        self.tp_unsynchronized.lock().await.total()
+    }
+
+    pub async fn reset_total(&self) {
+        self.tp_unsynchronized.lock().await.reset_total();
+    }
+
+    pub async fn event_count(&self) -> u64 {
+        self.tp_unsynchronized.lock().await.event_count()
+    }
+
+    pub async fn has_data(&self) -> bool {
+        self.tp_unsynchronized.lock().await.has_data()
+    }
+
+    pub async fn is_stalled(&self, max_idle: Duration) -> bool {
+        self.tp_unsynchronized.lock().await.is_stalled(max_idle)
+    }
+
+    pub async fn average_value(&self) -> Option<f64> {
+        self.tp_unsynchronized.lock().await.average_value()
+    }
+
+    pub async fn average_latency(&self) -> Option<Duration> {
+        self.tp_unsynchronized.lock().await.average_latency()
+    }
+
+    pub async fn elapsed(&self) -> Duration {
+        self.tp_unsynchronized.lock().await.elapsed()
+    }
+
+    #[must_use]
+    pub async fn peek_throughput(&self) -> Option<f64> {
+        self.tp_unsynchronized.lock().await.peek_throughput()
+    }
+
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub async fn throughput(&self) -> Option<f64> {
+        self.tp_unsynchronized.lock().await.throughput()
+    }
+
+    pub async fn try_throughput(&self) -> Result<f64, ThroughputError> {
+        self.tp_unsynchronized.lock().await.try_throughput()
+    }
+
+    /// See [`ThroughputSynchronized::snapshot`](crate::ThroughputSynchronized::snapshot).
+    #[must_use]
+    pub async fn snapshot(&self) -> Snapshot<V> {
+        self.tp_unsynchronized.lock().await.peek_snapshot()
+    }
+
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub async fn reset_and_report(&self) -> Option<f64> {
+        self.tp_unsynchronized.lock().await.reset_and_report()
+    }
+}
+
+impl<T: TimeSource, L: AsyncLock<Throughput<T, V, A>>, V: Accumulate, A: Aggregator<V>> Default
+    for ThroughputAsyncSynchronized<T, L, V, A>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::future::{self, Future};
+    use std::sync::{Mutex, MutexGuard};
+    use std::time::Duration;
+
+    use super::{AsyncLock, ThroughputAsyncSynchronized};
+    use crate::testing::ManualClock;
+    use crate::tp::{Throughput, TimeSource};
+
+    // a lock which resolves immediately, standing in for a custom runtime
+    struct BlockingLock<X>(Mutex<X>);
+
+    impl<X> AsyncLock<X> for BlockingLock<X> {
+        type Guard<'a>
+            = MutexGuard<'a, X>
+        where
+            X: 'a;
+
+        fn new(value: X) -> Self {
+            BlockingLock(Mutex::new(value))
+        }
+
+        fn lock(&self) -> impl Future<Output = Self::Guard<'_>> {
+            future::ready(self.0.lock().unwrap())
+        }
+
+        fn into_inner(self) -> X {
+            self.0.into_inner().unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_lock() {
+        let clock = ManualClock::now();
+        let tp: ThroughputAsyncSynchronized<ManualClock, BlockingLock<Throughput<ManualClock>>> =
+            ThroughputAsyncSynchronized::new();
+        tp.report(1).await;
+        tp.report(2).await;
+        clock.advance(Duration::from_secs(10));
+
+        assert_eq!(tp.count().await, 3);
+        assert_approx_eq!(tp.throughput().await.unwrap(), 0.3);
+        assert_eq!(tp.count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_into_inner() {
+        let tp: ThroughputAsyncSynchronized<ManualClock, BlockingLock<Throughput<ManualClock>>> =
+            ThroughputAsyncSynchronized::new();
+        tp.report(3).await;
+
+        assert_eq!(tp.into_inner().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_report_many() {
+        let tp: ThroughputAsyncSynchronized<ManualClock, BlockingLock<Throughput<ManualClock>>> =
+            ThroughputAsyncSynchronized::new();
+        tp.report_many(&[1, 2, 3]).await;
+        tp.report_iter(4..=6).await;
+
+        assert_eq!(tp.count().await, 21);
+        assert_eq!(tp.event_count().await, 6);
+    }
+}
@@ -0,0 +1,238 @@
+use std::option::Option;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Lock-free throughput measurement based on [`Instant`].
+///
+/// `report` is a compare-and-swap loop on the sum, so unlike
+/// [`ThroughputSynchronized`](crate::ThroughputSynchronized) producers never
+/// block on a lock. The sum saturates at `u64::MAX` like that of
+/// [`Throughput`](crate::Throughput). Reports which race
+/// with [`throughput`](Self::throughput) are attributed to either the closed
+/// or the new window, but never lost.
+#[derive(Debug)]
+pub struct ThroughputAtomic {
+    base: Instant,
+    // start of the current window in nanoseconds since `base`
+    start: AtomicU64,
+    sum: AtomicU64,
+}
+
+impl ThroughputAtomic {
+    pub fn new() -> Self {
+        Self {
+            base: Instant::now(),
+            start: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+        }
+    }
+
+    fn nanos_since_base(&self) -> u64 {
+        self.base.elapsed().as_nanos() as u64
+    }
+
+    fn rate(sum: u64, start: u64, now: u64) -> Option<f64> {
+        let denominator = Duration::from_nanos(now.saturating_sub(start)).as_secs_f64();
+        if denominator == 0.0 {
+            None
+        } else {
+            Some(sum as f64 / denominator)
+        }
+    }
+
+    pub fn report(&self, value: u64) {
+        // the closure never fails, so neither does the update
+        let _ = self
+            .sum
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |sum| {
+                Some(sum.saturating_add(value))
+            });
+    }
+
+    pub fn reset(&self) {
+        self.start.store(self.nanos_since_base(), Ordering::Release);
+        self.sum.store(0, Ordering::Release);
+    }
+
+    /// Returns the sum reported in the current window.
+    pub fn count(&self) -> u64 {
+        self.sum.load(Ordering::Acquire)
+    }
+
+    /// Returns how long the current window has been running.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(
+            self.nanos_since_base()
+                .saturating_sub(self.start.load(Ordering::Acquire)),
+        )
+    }
+
+    /// Returns the rate of the current window without resetting it.
+    #[must_use]
+    pub fn peek_throughput(&self) -> Option<f64> {
+        let start = self.start.load(Ordering::Acquire);
+        Self::rate(self.count(), start, self.nanos_since_base())
+    }
+
+    /// Returns the rate of the current window and starts a new one.
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput(&self) -> Option<f64> {
+        let now = self.nanos_since_base();
+        let start = self.start.swap(now, Ordering::AcqRel);
+        let sum = self.sum.swap(0, Ordering::AcqRel);
+
+        Self::rate(sum, start, now)
+    }
+
+    /// Splits into a handle for producers and one for the consumer, so only
+    /// the latter can close windows. The recorder can be cloned for every
+    /// producer, the reader cannot.
+    pub fn split(self) -> (AtomicRecorder, AtomicReader) {
+        let tp = Arc::new(self);
+        (AtomicRecorder { tp: tp.clone() }, AtomicReader { tp })
+    }
+}
+
+impl Default for ThroughputAtomic {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reporting half of a [`ThroughputAtomic`], see
+/// [`ThroughputAtomic::split`].
+#[derive(Clone, Debug)]
+pub struct AtomicRecorder {
+    tp: Arc<ThroughputAtomic>,
+}
+
+impl AtomicRecorder {
+    pub fn report(&self, value: u64) {
+        self.tp.report(value);
+    }
+}
+
+/// Reading half of a [`ThroughputAtomic`], see [`ThroughputAtomic::split`].
+#[derive(Debug)]
+pub struct AtomicReader {
+    tp: Arc<ThroughputAtomic>,
+}
+
+impl AtomicReader {
+    pub fn reset(&self) {
+        self.tp.reset();
+    }
+
+    /// See [`ThroughputAtomic::count`].
+    pub fn count(&self) -> u64 {
+        self.tp.count()
+    }
+
+    /// See [`ThroughputAtomic::elapsed`].
+    pub fn elapsed(&self) -> Duration {
+        self.tp.elapsed()
+    }
+
+    /// See [`ThroughputAtomic::peek_throughput`].
+    #[must_use]
+    pub fn peek_throughput(&self) -> Option<f64> {
+        self.tp.peek_throughput()
+    }
+
+    /// See [`ThroughputAtomic::throughput`].
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput(&self) -> Option<f64> {
+        self.tp.throughput()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::ThroughputAtomic;
+
+    #[test]
+    fn test_basic() {
+        let tp = ThroughputAtomic::new();
+        tp.report(3);
+        tp.report(4);
+        assert_eq!(tp.count(), 7);
+
+        thread::sleep(Duration::from_millis(1));
+        assert!(tp.peek_throughput().unwrap() > 0.0);
+        assert!(tp.throughput().unwrap() > 0.0);
+        assert_eq!(tp.count(), 0);
+
+        tp.report(1);
+        tp.reset();
+        assert_eq!(tp.count(), 0);
+    }
+
+    #[test]
+    fn test_saturates() {
+        let tp = ThroughputAtomic::new();
+        tp.report(u64::MAX - 1);
+        tp.report(5);
+        assert_eq!(tp.count(), u64::MAX);
+    }
+
+    #[test]
+    fn test_stress() {
+        const THREADS: u64 = 8;
+        const REPORTS: u64 = 100_000;
+
+        let tp = Arc::new(ThroughputAtomic::new());
+        let producers: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let tp = tp.clone();
+                thread::spawn(move || {
+                    for _ in 0..REPORTS {
+                        tp.report(2);
+                    }
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        assert_eq!(tp.count(), THREADS * REPORTS * 2);
+    }
+
+    #[test]
+    fn test_split() {
+        const THREADS: u64 = 4;
+        const REPORTS: u64 = 10_000;
+
+        let (recorder, reader) = ThroughputAtomic::new().split();
+        let producers: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let recorder = recorder.clone();
+                thread::spawn(move || {
+                    for _ in 0..REPORTS {
+                        recorder.report(3);
+                    }
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        assert_eq!(reader.count(), THREADS * REPORTS * 3);
+        thread::sleep(Duration::from_millis(1));
+        assert!(reader.peek_throughput().unwrap() > 0.0);
+        assert!(reader.throughput().unwrap() > 0.0);
+        assert_eq!(reader.count(), 0);
+
+        recorder.report(1);
+        reader.reset();
+        assert_eq!(reader.count(), 0);
+    }
+}
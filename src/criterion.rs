@@ -0,0 +1,26 @@
+/// Converts a byte count reported to a throughput, e.g. the
+/// [`count`](crate::Throughput::count) of one benchmark iteration, into the
+/// throughput criterion reports alongside the timing, see
+/// `BenchmarkGroup::throughput`.
+pub fn to_criterion_throughput(bytes: u64) -> ::criterion::Throughput {
+    ::criterion::Throughput::Bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_criterion_throughput;
+    use crate::testing::ManualClock;
+    use crate::tp::Throughput;
+
+    #[test]
+    fn test_to_criterion_throughput() {
+        let mut tp: Throughput<ManualClock> = Throughput::new();
+        tp.report(512);
+        tp.report(512);
+
+        assert!(matches!(
+            to_criterion_throughput(tp.count()),
+            ::criterion::Throughput::Bytes(1024)
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+use core::marker::PhantomData;
+use core::option::Option;
+use core::time::Duration;
+
+use crate::tp::{Accumulate, TimeSource};
+
+/// Throughput where older reports contribute less and less.
+///
+/// Before each report the accumulated sum is multiplied by
+/// `0.5^(dt / half_life)`, so a report counts half after one half-life. The
+/// rate refers to the effective window of the decay, which is
+/// `half_life / ln 2` once the throughput has been running for a few
+/// half-lives.
+#[derive(Debug)]
+pub struct DecayingThroughput<T: TimeSource, V: Accumulate = u64> {
+    start: T,
+    // time the sum was last decayed at, the start until the first report
+    last_decay: Option<T>,
+    half_life: Duration,
+    sum: f64,
+    _marker: PhantomData<fn(V)>,
+}
+
+impl<T: TimeSource, V: Accumulate> DecayingThroughput<T, V> {
+    /// # Panics
+    ///
+    /// Panics if `half_life` is zero.
+    pub fn new(half_life: Duration) -> Self {
+        Self::starting_at(half_life, T::now())
+    }
+
+    /// Like [`new`](Self::new), but starts at `start` rather than at
+    /// [`T::now`](TimeSource::now), see
+    /// [`Throughput::starting_at`](crate::Throughput::starting_at).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `half_life` is zero.
+    pub fn starting_at(half_life: Duration, start: T) -> Self {
+        assert!(!half_life.is_zero(), "half_life must be non-zero");
+
+        Self {
+            start,
+            last_decay: None,
+            half_life,
+            sum: 0.0,
+            _marker: PhantomData,
+        }
+    }
+
+    fn decay_factor(&self, elapsed: Duration) -> f64 {
+        0.5f64.powf(elapsed.as_secs_f64() / self.half_life.as_secs_f64())
+    }
+
+    pub fn report(&mut self, value: V) {
+        self.sum = self.sum() + value.to_f64();
+        self.last_decay = Some(self.start.renew());
+    }
+
+    /// Returns the sum of all reports, each decayed according to its age.
+    pub fn sum(&self) -> f64 {
+        let last_decay = self.last_decay.as_ref().unwrap_or(&self.start);
+        self.sum * self.decay_factor(last_decay.elapsed())
+    }
+
+    /// Returns the decayed sum divided by the effective window, or `None`
+    /// if no time elapsed since construction.
+    #[must_use]
+    pub fn rate(&self) -> Option<f64> {
+        let mean_life = self.half_life.as_secs_f64() / core::f64::consts::LN_2;
+        let window = mean_life * (1.0 - self.decay_factor(self.start.elapsed()));
+        if window == 0.0 {
+            None
+        } else {
+            Some(self.sum() / window)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::time::Duration;
+
+    use super::DecayingThroughput;
+    use crate::testing::ManualClock;
+    use crate::tp::TimeSource;
+
+    #[test]
+    fn test_half_life() {
+        let clock = ManualClock::now();
+        let mut tp: DecayingThroughput<ManualClock> =
+            DecayingThroughput::new(Duration::from_secs(2));
+        assert_eq!(None, tp.rate());
+
+        tp.report(100);
+        clock.advance(Duration::from_secs(2));
+        assert_approx_eq!(tp.sum(), 50.0);
+
+        tp.report(50);
+        clock.advance(Duration::from_secs(4));
+        assert_approx_eq!(tp.sum(), 25.0);
+    }
+
+    #[test]
+    fn test_starting_at() {
+        // only the independent clock advances, so decaying has to renew it
+        let clock = ManualClock::new();
+        let mut tp: DecayingThroughput<ManualClock> =
+            DecayingThroughput::starting_at(Duration::from_secs(2), clock.clone());
+
+        tp.report(100);
+        clock.advance(Duration::from_secs(2));
+        assert_approx_eq!(tp.sum(), 50.0);
+
+        tp.report(50);
+        clock.advance(Duration::from_secs(2));
+        assert_approx_eq!(tp.sum(), 50.0);
+    }
+
+    #[test]
+    fn test_steady_rate() {
+        let clock = ManualClock::now();
+        let mut tp: DecayingThroughput<ManualClock> =
+            DecayingThroughput::new(Duration::from_secs(1));
+
+        for _ in 0..200 {
+            clock.advance(Duration::from_millis(100));
+            tp.report(10);
+        }
+
+        assert_approx_eq!(tp.rate().unwrap(), 100.0, 5.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_half_life() {
+        let _: DecayingThroughput<ManualClock> = DecayingThroughput::new(Duration::default());
+    }
+}
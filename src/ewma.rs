@@ -0,0 +1,111 @@
+use core::fmt;
+use core::option::Option;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::tp::{Accumulate, Throughput, TimeSource};
+
+/// Error returned by [`EwmaThroughput::new`] when alpha is not within
+/// `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidAlphaError(pub f64);
+
+impl fmt::Display for InvalidAlphaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "alpha {} is not within 0.0..=1.0", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for InvalidAlphaError {}
+
+/// Exponentially weighted moving average of the window rates of a
+/// [`Throughput`].
+///
+/// Every [`tick`](EwmaThroughput::tick) closes the current window and folds
+/// its rate into the average as `alpha * rate + (1 - alpha) * average`.
+#[derive(Debug)]
+pub struct EwmaThroughput<T: TimeSource, V: Accumulate = u64> {
+    tp: Throughput<T, V>,
+    alpha: f64,
+    average: Option<f64>,
+}
+
+impl<T: TimeSource, V: Accumulate> EwmaThroughput<T, V> {
+    pub fn new(alpha: f64) -> Result<Self, InvalidAlphaError> {
+        if !(0.0..=1.0).contains(&alpha) {
+            return Err(InvalidAlphaError(alpha));
+        }
+
+        Ok(Self {
+            tp: Throughput::new(),
+            alpha,
+            average: None,
+        })
+    }
+
+    pub fn report(&mut self, value: V) {
+        self.tp.report(value);
+    }
+
+    /// Closes the current window and returns the updated average. A window
+    /// without a rate leaves the average untouched.
+    pub fn tick(&mut self) -> Option<f64> {
+        if let Some(rate) = self.tp.throughput() {
+            self.average = Some(match self.average {
+                Some(average) => self.alpha * rate + (1.0 - self.alpha) * average,
+                None => rate,
+            });
+        }
+
+        self.average
+    }
+
+    pub fn average(&self) -> Option<f64> {
+        self.average
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::time::Duration;
+
+    use super::{EwmaThroughput, InvalidAlphaError};
+    use crate::testing::ManualClock;
+    use crate::tp::TimeSource;
+
+    #[test]
+    fn test_invalid_alpha() {
+        assert_eq!(
+            Some(InvalidAlphaError(1.5)),
+            EwmaThroughput::<ManualClock>::new(1.5).err()
+        );
+        assert!(EwmaThroughput::<ManualClock>::new(-0.1).is_err());
+        assert!(EwmaThroughput::<ManualClock>::new(f64::NAN).is_err());
+        assert!(EwmaThroughput::<ManualClock>::new(0.0).is_ok());
+        assert!(EwmaThroughput::<ManualClock>::new(1.0).is_ok());
+    }
+
+    #[test]
+    fn test_converges() {
+        let clock = ManualClock::now();
+        let mut tp: EwmaThroughput<ManualClock> = EwmaThroughput::new(0.5).unwrap();
+        assert_eq!(None, tp.average());
+
+        clock.advance(Duration::from_secs(1));
+        assert_approx_eq!(tp.tick().unwrap(), 0.0);
+
+        let mut previous = 0.0;
+        for _ in 0..20 {
+            tp.report(10);
+            clock.advance(Duration::from_secs(1));
+            let average = tp.tick().unwrap();
+            assert!(average > previous);
+            previous = average;
+        }
+
+        assert_approx_eq!(tp.average().unwrap(), 10.0, 1e-3);
+    }
+}
@@ -0,0 +1,101 @@
+use alloc::format;
+use alloc::string::String;
+
+/// Prefix scaling used by [`format_bytes_per_sec`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteScale {
+    /// IEC prefixes with a base of 1024, e.g. `MiB/s`.
+    Binary,
+    /// SI prefixes with a base of 1000, e.g. `MB/s`.
+    Decimal,
+}
+
+const BINARY_UNITS: [&str; 4] = ["B/s", "KiB/s", "MiB/s", "GiB/s"];
+const DECIMAL_UNITS: [&str; 4] = ["B/s", "kB/s", "MB/s", "GB/s"];
+const BIT_UNITS: [&str; 5] = ["bit/s", "kbit/s", "Mbit/s", "Gbit/s", "Tbit/s"];
+
+fn format_scaled(rate: f64, base: f64, units: &[&str], decimals: usize) -> String {
+    let mut scaled = rate;
+    let mut unit = 0;
+    while scaled.abs() >= base && unit + 1 < units.len() {
+        scaled /= base;
+        unit += 1;
+    }
+
+    format!("{:.*} {}", decimals, scaled, units[unit])
+}
+
+/// Formats a rate in bytes per second with two decimal places, using the
+/// largest prefix which keeps the value at or above one.
+pub fn format_bytes_per_sec(rate: f64, scale: ByteScale) -> String {
+    match scale {
+        ByteScale::Binary => format_scaled(rate, 1024.0, &BINARY_UNITS, 2),
+        ByteScale::Decimal => format_scaled(rate, 1000.0, &DECIMAL_UNITS, 2),
+    }
+}
+
+/// Formats a rate in bits per second with one decimal place and SI
+/// prefixes, e.g. `100.0 Mbit/s`, as used for network links. See
+/// [`Throughput::throughput_bits`](crate::Throughput::throughput_bits) for
+/// the rate of a byte count.
+pub fn format_bits_per_sec(rate: f64) -> String {
+    format_scaled(rate, 1000.0, &BIT_UNITS, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_bits_per_sec, format_bytes_per_sec, ByteScale};
+
+    #[test]
+    fn test_binary() {
+        assert_eq!(format_bytes_per_sec(0.0, ByteScale::Binary), "0.00 B/s");
+        assert_eq!(
+            format_bytes_per_sec(1023.0, ByteScale::Binary),
+            "1023.00 B/s"
+        );
+        assert_eq!(
+            format_bytes_per_sec(1024.0, ByteScale::Binary),
+            "1.00 KiB/s"
+        );
+        assert_eq!(
+            format_bytes_per_sec(1_000_000.0, ByteScale::Binary),
+            "976.56 KiB/s"
+        );
+        assert_eq!(
+            format_bytes_per_sec(12.3 * 1024.0 * 1024.0, ByteScale::Binary),
+            "12.30 MiB/s"
+        );
+        assert_eq!(
+            format_bytes_per_sec(2048.0 * 1024.0 * 1024.0 * 1024.0, ByteScale::Binary),
+            "2048.00 GiB/s"
+        );
+    }
+
+    #[test]
+    fn test_decimal() {
+        assert_eq!(
+            format_bytes_per_sec(999.0, ByteScale::Decimal),
+            "999.00 B/s"
+        );
+        assert_eq!(
+            format_bytes_per_sec(1024.0, ByteScale::Decimal),
+            "1.02 kB/s"
+        );
+        assert_eq!(
+            format_bytes_per_sec(1_000_000.0, ByteScale::Decimal),
+            "1.00 MB/s"
+        );
+        assert_eq!(
+            format_bytes_per_sec(1_500_000_000.0, ByteScale::Decimal),
+            "1.50 GB/s"
+        );
+    }
+
+    #[test]
+    fn test_bits() {
+        assert_eq!(format_bits_per_sec(999.0), "999.0 bit/s");
+        assert_eq!(format_bits_per_sec(100_000_000.0), "100.0 Mbit/s");
+        assert_eq!(format_bits_per_sec(125_000_000.0 * 8.0), "1.0 Gbit/s");
+        assert_eq!(format_bits_per_sec(2.5e12), "2.5 Tbit/s");
+    }
+}
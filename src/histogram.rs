@@ -0,0 +1,81 @@
+/// Distribution of report values over `N` exponential buckets.
+///
+/// Bucket `0` counts values below `1`, bucket `i` values in
+/// `[2^(i-1), 2^i)`. The last bucket additionally counts all larger values.
+/// It shows whether throughput is driven by many small or few large
+/// reports, see [`Throughput::record_histogram`](crate::Throughput::record_histogram).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueHistogram<const N: usize> {
+    buckets: [u64; N],
+}
+
+impl<const N: usize> ValueHistogram<N> {
+    pub fn new() -> Self {
+        assert!(N > 0, "histogram needs at least one bucket");
+
+        Self { buckets: [0; N] }
+    }
+
+    /// Counts `value` in its bucket.
+    pub fn record(&mut self, value: f64) {
+        let bucket = if value.is_nan() || value < 1.0 {
+            0
+        } else {
+            // the biased exponent is floor(log2(value)) + 1023
+            ((value.to_bits() >> 52) as usize & 0x7ff) - 1022
+        };
+
+        let count = &mut self.buckets[bucket.min(N - 1)];
+        *count = count.saturating_add(1);
+    }
+
+    /// Returns the number of recorded values per bucket.
+    pub fn buckets(&self) -> [u64; N] {
+        self.buckets
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets = [0; N];
+    }
+}
+
+impl<const N: usize> Default for ValueHistogram<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ValueHistogram;
+
+    #[test]
+    fn test_buckets() {
+        let mut histogram: ValueHistogram<5> = ValueHistogram::new();
+        for value in &[
+            0.0,
+            0.5,
+            1.0,
+            2.0,
+            3.0,
+            4.0,
+            7.0,
+            8.0,
+            1000.0,
+            f64::INFINITY,
+        ] {
+            histogram.record(*value);
+        }
+
+        assert_eq!(histogram.buckets(), [2, 1, 2, 2, 3]);
+
+        histogram.clear();
+        assert_eq!(histogram.buckets(), [0; 5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_no_buckets() {
+        let _: ValueHistogram<0> = ValueHistogram::new();
+    }
+}
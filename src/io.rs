@@ -0,0 +1,259 @@
+use std::io::{self, Read, Write};
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::sync::Arc;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "async")]
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::tp::{ThroughputSynchronized, TimeSource};
+
+/// Wraps a [`Read`] and reports the number of bytes read by every
+/// successful `read` call. Reads at the end of the input return no bytes
+/// and are not reported, so they don't count as events.
+#[derive(Debug)]
+pub struct ThroughputReader<R, T: TimeSource> {
+    inner: R,
+    tp: Arc<ThroughputSynchronized<T>>,
+}
+
+impl<R: Read, T: TimeSource> ThroughputReader<R, T> {
+    pub fn new(inner: R, tp: Arc<ThroughputSynchronized<T>>) -> Self {
+        Self { inner, tp }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read, T: TimeSource> Read for ThroughputReader<R, T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.tp.report(n as u64);
+        }
+
+        Ok(n)
+    }
+}
+
+/// Wraps a [`Write`] and reports the number of bytes written by every
+/// successful `write` call.
+#[derive(Debug)]
+pub struct ThroughputWriter<W, T: TimeSource> {
+    inner: W,
+    tp: Arc<ThroughputSynchronized<T>>,
+}
+
+impl<W: Write, T: TimeSource> ThroughputWriter<W, T> {
+    pub fn new(inner: W, tp: Arc<ThroughputSynchronized<T>>) -> Self {
+        Self { inner, tp }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write, T: TimeSource> Write for ThroughputWriter<W, T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.tp.report(n as u64);
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Wraps a tokio [`AsyncRead`] and reports the number of bytes read by
+/// every poll which makes progress. Polls at the end of the input are not
+/// reported, like with [`ThroughputReader`].
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct AsyncThroughputReader<R, T: TimeSource> {
+    inner: R,
+    tp: Arc<ThroughputSynchronized<T>>,
+}
+
+#[cfg(feature = "async")]
+impl<R: AsyncRead + Unpin, T: TimeSource> AsyncThroughputReader<R, T> {
+    pub fn new(inner: R, tp: Arc<ThroughputSynchronized<T>>) -> Self {
+        Self { inner, tp }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: AsyncRead + Unpin, T: TimeSource> AsyncRead for AsyncThroughputReader<R, T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let n = buf.filled().len() - filled;
+        if n > 0 && matches!(poll, Poll::Ready(Ok(()))) {
+            self.tp.report(n as u64);
+        }
+
+        poll
+    }
+}
+
+/// Wraps a tokio [`AsyncWrite`] and reports the number of bytes written by
+/// every poll which makes progress.
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct AsyncThroughputWriter<W, T: TimeSource> {
+    inner: W,
+    tp: Arc<ThroughputSynchronized<T>>,
+}
+
+#[cfg(feature = "async")]
+impl<W: AsyncWrite + Unpin, T: TimeSource> AsyncThroughputWriter<W, T> {
+    pub fn new(inner: W, tp: Arc<ThroughputSynchronized<T>>) -> Self {
+        Self { inner, tp }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(feature = "async")]
+impl<W: AsyncWrite + Unpin, T: TimeSource> AsyncWrite for AsyncThroughputWriter<W, T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            self.tp.report(n as u64);
+        }
+
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor, Read, Write};
+    use std::sync::Arc;
+
+    use super::{ThroughputReader, ThroughputWriter};
+    use crate::testing::ManualClock;
+    use crate::tp::ThroughputSynchronized;
+
+    #[test]
+    fn test_reader() {
+        let tp: Arc<ThroughputSynchronized<ManualClock>> = Arc::new(ThroughputSynchronized::new());
+        let mut reader = ThroughputReader::new(Cursor::new(vec![7u8; 100]), tp.clone());
+
+        let mut buf = [0u8; 30];
+        assert_eq!(reader.read(&mut buf).unwrap(), 30);
+        assert_eq!(tp.count(), 30);
+
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest.len(), 70);
+        assert_eq!(tp.count(), 100);
+
+        assert_eq!(reader.into_inner().position(), 100);
+    }
+
+    #[test]
+    fn test_reader_eof() {
+        let tp: Arc<ThroughputSynchronized<ManualClock>> = Arc::new(ThroughputSynchronized::new());
+        let mut reader = ThroughputReader::new(Cursor::new(vec![7u8; 10]), tp.clone());
+
+        let mut buf = [0u8; 30];
+        assert_eq!(reader.read(&mut buf).unwrap(), 10);
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+
+        assert_eq!(tp.count(), 10);
+        assert_eq!(tp.event_count(), 1);
+        assert_eq!(tp.average_value(), Some(10.0));
+    }
+
+    #[test]
+    fn test_writer() {
+        let tp: Arc<ThroughputSynchronized<ManualClock>> = Arc::new(ThroughputSynchronized::new());
+        let mut writer = ThroughputWriter::new(Cursor::new(Vec::new()), tp.clone());
+
+        writer.write_all(b"hello world").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(tp.count(), 11);
+
+        io::copy(&mut Cursor::new(vec![0u8; 1000]), &mut writer).unwrap();
+        assert_eq!(tp.count(), 1011);
+        assert_eq!(writer.into_inner().into_inner().len(), 1011);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_duplex() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        use super::{AsyncThroughputReader, AsyncThroughputWriter};
+
+        let read_tp: Arc<ThroughputSynchronized<ManualClock>> =
+            Arc::new(ThroughputSynchronized::new());
+        let write_tp: Arc<ThroughputSynchronized<ManualClock>> =
+            Arc::new(ThroughputSynchronized::new());
+
+        // a small pipe forces partial reads and writes
+        let (client, server) = tokio::io::duplex(64);
+        let mut writer = AsyncThroughputWriter::new(client, write_tp.clone());
+        let mut reader = AsyncThroughputReader::new(server, read_tp.clone());
+
+        let producer = tokio::spawn(async move {
+            writer.write_all(&[1u8; 1000]).await.unwrap();
+            writer.shutdown().await.unwrap();
+        });
+
+        let mut received = Vec::new();
+        reader.read_to_end(&mut received).await.unwrap();
+        producer.await.unwrap();
+
+        assert_eq!(received.len(), 1000);
+        assert_eq!(read_tp.count(), 1000);
+        assert_eq!(write_tp.count(), 1000);
+        assert!(read_tp.event_count() > 1);
+    }
+}
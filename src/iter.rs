@@ -0,0 +1,90 @@
+use crate::tp::{ThroughputSynchronized, TimeSource};
+
+/// Iterator adapter reporting a count for every item yielded by the wrapped
+/// iterator, see [`ThroughputIterExt`].
+#[derive(Debug)]
+pub struct ThroughputIter<'a, I, T: TimeSource, F> {
+    inner: I,
+    tp: &'a ThroughputSynchronized<T>,
+    count: F,
+}
+
+impl<I: Iterator, T: TimeSource, F: FnMut(&I::Item) -> u64> Iterator
+    for ThroughputIter<'_, I, T, F>
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        self.tp.report((self.count)(&item));
+
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Count function used by [`ThroughputIterExt::throughput`].
+pub type CountOne<X> = fn(&X) -> u64;
+
+fn one<X>(_: &X) -> u64 {
+    1
+}
+
+pub trait ThroughputIterExt: Iterator + Sized {
+    /// Reports `1` to `tp` for every item of this iterator.
+    fn throughput<T: TimeSource>(
+        self,
+        tp: &ThroughputSynchronized<T>,
+    ) -> ThroughputIter<'_, Self, T, CountOne<Self::Item>> {
+        self.throughput_by(tp, one)
+    }
+
+    /// Reports `count(&item)` to `tp` for every item of this iterator.
+    fn throughput_by<T: TimeSource, F: FnMut(&Self::Item) -> u64>(
+        self,
+        tp: &ThroughputSynchronized<T>,
+        count: F,
+    ) -> ThroughputIter<'_, Self, T, F> {
+        ThroughputIter {
+            inner: self,
+            tp,
+            count,
+        }
+    }
+}
+
+impl<I: Iterator> ThroughputIterExt for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::ThroughputIterExt;
+    use crate::testing::ManualClock;
+    use crate::tp::ThroughputSynchronized;
+
+    #[test]
+    fn test_iter() {
+        let tp: ThroughputSynchronized<ManualClock> = ThroughputSynchronized::new();
+
+        let sum: u32 = (0..100).throughput(&tp).sum();
+        assert_eq!(sum, 4950);
+        assert_eq!(tp.count(), 100);
+        assert_eq!(tp.event_count(), 100);
+    }
+
+    #[test]
+    fn test_iter_by() {
+        let tp: ThroughputSynchronized<ManualClock> = ThroughputSynchronized::new();
+        let chunks = [vec![0u8; 3], vec![0u8; 5]];
+
+        let lens: Vec<_> = chunks
+            .iter()
+            .throughput_by(&tp, |chunk| chunk.len() as u64)
+            .map(Vec::len)
+            .collect();
+        assert_eq!(lens, vec![3, 5]);
+        assert_eq!(tp.count(), 8);
+    }
+}
@@ -1,5 +1,102 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(rust_2018_idioms)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod aggregate;
+pub mod async_lock;
+#[cfg(feature = "std")]
+pub mod atomic;
+#[cfg(feature = "criterion")]
+mod criterion;
+#[cfg(feature = "std")]
+pub mod decay;
+pub mod ewma;
+#[cfg(feature = "alloc")]
+pub mod format;
+pub mod histogram;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "std")]
+pub mod iter;
+pub mod limiter;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "opentelemetry")]
+mod opentelemetry;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+#[cfg(feature = "alloc")]
+pub mod quantiles;
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "alloc")]
+mod ring;
+#[cfg(feature = "std")]
+pub mod sharded;
+#[cfg(feature = "statsd")]
+mod statsd;
+#[cfg(feature = "futures")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod testing;
+#[cfg(feature = "alloc")]
+pub mod timeseries;
 pub mod tp;
+#[cfg(feature = "tracing")]
+mod tracing;
+pub mod unit;
+#[cfg(feature = "alloc")]
+pub mod window;
 
-pub use crate::tp::{Throughput, ThroughputSynchronized};
+pub use crate::aggregate::{Aggregator, Count, MaxValue, SumPerSecond};
+pub use crate::async_lock::{AsyncLock, ThroughputAsyncSynchronized};
+#[cfg(feature = "std")]
+pub use crate::atomic::{AtomicReader, AtomicRecorder, ThroughputAtomic};
+#[cfg(feature = "criterion")]
+pub use crate::criterion::to_criterion_throughput;
+#[cfg(feature = "std")]
+pub use crate::decay::DecayingThroughput;
+pub use crate::ewma::{EwmaThroughput, InvalidAlphaError};
+#[cfg(feature = "alloc")]
+pub use crate::format::{format_bits_per_sec, format_bytes_per_sec, ByteScale};
+pub use crate::histogram::ValueHistogram;
+#[cfg(feature = "async")]
+pub use crate::io::{AsyncThroughputReader, AsyncThroughputWriter};
+#[cfg(feature = "std")]
+pub use crate::io::{ThroughputReader, ThroughputWriter};
+#[cfg(feature = "std")]
+pub use crate::iter::{ThroughputIter, ThroughputIterExt};
+pub use crate::limiter::RateLimiter;
+#[cfg(feature = "opentelemetry")]
+pub use crate::opentelemetry::register_observable_gauge;
+#[cfg(feature = "prometheus")]
+pub use crate::prometheus::ThroughputGauge;
+#[cfg(feature = "alloc")]
+pub use crate::quantiles::RateQuantiles;
+#[cfg(feature = "std")]
+pub use crate::registry::ThroughputRegistry;
+#[cfg(feature = "std")]
+pub use crate::sharded::ShardedThroughput;
+#[cfg(feature = "statsd")]
+pub use crate::statsd::StatsdSink;
+#[cfg(feature = "futures")]
+pub use crate::stream::{ThroughputStream, ThroughputStreamExt};
+#[cfg(feature = "alloc")]
+pub use crate::timeseries::TimeSeries;
+#[cfg(feature = "async")]
+pub use crate::tp::tokio_async::spawn_interval_reporter;
+#[cfg(feature = "alloc")]
+pub use crate::tp::Threshold;
+pub use crate::tp::{
+    combined_throughput, Accumulate, OverflowError, RateUnit, Snapshot, Throughput,
+    ThroughputBuilder, ThroughputError, ThroughputReport, ThroughputState, HISTOGRAM_BUCKETS,
+};
+#[cfg(feature = "std")]
+pub use crate::tp::{
+    LazyThroughputSynchronized, LogOnDrop, ReportGuard, ThroughputRwLock, ThroughputSynchronized,
+};
+pub use crate::unit::{Bytes, Items, Unit, UnitThroughput};
+#[cfg(feature = "alloc")]
+pub use crate::window::{MultiWindow, WindowedThroughput};
@@ -0,0 +1,115 @@
+use core::option::Option;
+
+use crate::tp::{Accumulate, Throughput, TimeSource};
+
+/// Token bucket limiting reported values to a target rate.
+///
+/// The bucket holds up to `burst` tokens and is refilled with `rate` tokens
+/// per second. [`allow`](RateLimiter::allow) admits a value if enough
+/// tokens are left, so the admitted throughput stays at or below `rate`
+/// over any window, apart from an initial burst of up to `burst`.
+#[derive(Debug)]
+pub struct RateLimiter<T: TimeSource, V: Accumulate = u64> {
+    last_refill: T,
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    admitted: Throughput<T, V>,
+}
+
+impl<T: TimeSource, V: Accumulate> RateLimiter<T, V> {
+    /// Creates a limiter with a full bucket.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is not positive or `burst` is negative.
+    pub fn new(rate: f64, burst: f64) -> Self {
+        assert!(rate > 0.0, "rate must be positive");
+        assert!(burst >= 0.0, "burst must not be negative");
+
+        Self {
+            last_refill: T::now(),
+            rate,
+            burst,
+            tokens: burst,
+            admitted: Throughput::new(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let elapsed = self.last_refill.elapsed().as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last_refill = self.last_refill.renew();
+    }
+
+    /// Returns whether `value` fits within the limit. Admitted values are
+    /// taken from the bucket and reported, rejected ones are not.
+    pub fn allow(&mut self, value: V) -> bool {
+        self.refill();
+
+        let cost = value.to_f64();
+        if cost > self.tokens {
+            return false;
+        }
+
+        self.tokens -= cost;
+        self.admitted.report(value);
+
+        true
+    }
+
+    /// Returns the rate of the admitted values and starts a new window.
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput(&mut self) -> Option<f64> {
+        self.admitted.throughput()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::time::Duration;
+
+    use super::RateLimiter;
+    use crate::testing::ManualClock;
+    use crate::tp::TimeSource;
+
+    #[test]
+    fn test_allow() {
+        let clock = ManualClock::now();
+        let mut limiter: RateLimiter<ManualClock> = RateLimiter::new(10.0, 10.0);
+        assert!(limiter.allow(10));
+        assert!(!limiter.allow(1));
+
+        clock.advance(Duration::from_millis(500));
+        assert!(!limiter.allow(6));
+        assert!(limiter.allow(5));
+        assert!(!limiter.allow(1));
+
+        // the bucket never holds more than the burst
+        clock.advance(Duration::from_secs(10));
+        assert!(limiter.allow(10));
+        assert!(!limiter.allow(1));
+    }
+
+    #[test]
+    fn test_throughput() {
+        let clock = ManualClock::now();
+        let mut limiter: RateLimiter<ManualClock> = RateLimiter::new(100.0, 1.0);
+
+        for _ in 0..100 {
+            clock.advance(Duration::from_millis(10));
+            assert!(limiter.allow(1));
+            assert!(!limiter.allow(1));
+        }
+
+        assert_approx_eq!(limiter.throughput().unwrap(), 100.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_rate() {
+        let _: RateLimiter<ManualClock> = RateLimiter::new(0.0, 1.0);
+    }
+}
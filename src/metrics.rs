@@ -0,0 +1,131 @@
+use std::option::Option;
+
+use crate::tp::{Accumulate, ThroughputSynchronized, TimeSource};
+
+impl<T: TimeSource, V: Accumulate> ThroughputSynchronized<T, V> {
+    /// Computes the rate of the current window, starts a new one and sets
+    /// the `metrics` gauge `name` to the rate. The [name](Self::name) of
+    /// the throughput, if any, is attached as the label `name`. Nothing is
+    /// emitted if the window has no rate.
+    pub fn report_to_metrics(&self, name: &'static str) -> Option<f64> {
+        let tp = self.throughput();
+        if let Some(rate) = tp {
+            match self.name() {
+                Some(label) => ::metrics::gauge!(name, "name" => label).set(rate),
+                None => ::metrics::gauge!(name).set(rate),
+            }
+        }
+
+        tp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use ::metrics::{
+        Counter, Gauge, GaugeFn, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+    };
+
+    use crate::testing::ManualClock;
+    use crate::tp::{Throughput, ThroughputSynchronized, TimeSource};
+
+    #[derive(Default)]
+    struct CapturedGauge {
+        values: Mutex<Vec<(String, f64)>>,
+        // labels of the registered gauges
+        labels: Mutex<Vec<(String, String)>>,
+    }
+
+    struct NamedGauge {
+        name: String,
+        captured: Arc<CapturedGauge>,
+    }
+
+    impl GaugeFn for NamedGauge {
+        fn increment(&self, _value: f64) {}
+
+        fn decrement(&self, _value: f64) {}
+
+        fn set(&self, value: f64) {
+            self.captured
+                .values
+                .lock()
+                .unwrap()
+                .push((self.name.clone(), value));
+        }
+    }
+
+    #[derive(Default)]
+    struct CapturingRecorder {
+        captured: Arc<CapturedGauge>,
+    }
+
+    impl Recorder for CapturingRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, _: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::noop()
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            self.captured.labels.lock().unwrap().extend(
+                key.labels()
+                    .map(|label| (label.key().to_owned(), label.value().to_owned())),
+            );
+            Gauge::from_arc(Arc::new(NamedGauge {
+                name: key.name().to_owned(),
+                captured: self.captured.clone(),
+            }))
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn test_report_to_metrics() {
+        let recorder = CapturingRecorder::default();
+        let clock = ManualClock::now();
+        let tp: ThroughputSynchronized<ManualClock> = ThroughputSynchronized::new();
+        tp.report(5);
+        clock.advance(Duration::from_secs(10));
+
+        let rate = ::metrics::with_local_recorder(&recorder, || tp.report_to_metrics("tp_rate"));
+
+        assert_approx_eq!(rate.unwrap(), 0.5);
+        let values = recorder.captured.values.lock().unwrap();
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].0, "tp_rate");
+        assert_approx_eq!(values[0].1, 0.5);
+        assert!(recorder.captured.labels.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_report_to_metrics_name() {
+        let recorder = CapturingRecorder::default();
+        let clock = ManualClock::now();
+        let tp: ThroughputSynchronized<ManualClock> =
+            Throughput::builder().name("uploads").build().into();
+        tp.report(5);
+        clock.advance(Duration::from_secs(10));
+
+        let rate = ::metrics::with_local_recorder(&recorder, || tp.report_to_metrics("tp_rate"));
+
+        assert_approx_eq!(rate.unwrap(), 0.5);
+        assert_eq!(recorder.captured.values.lock().unwrap()[0].0, "tp_rate");
+        assert_eq!(
+            *recorder.captured.labels.lock().unwrap(),
+            [("name".to_owned(), "uploads".to_owned())]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use ::opentelemetry::metrics::{Meter, ObservableGauge};
+
+use crate::tp::{ThroughputSynchronized, TimeSource};
+
+/// Registers the OpenTelemetry gauge `name` on `meter`, observing the rate
+/// of `tp`.
+///
+/// Every collection reads the rate with
+/// [`peek_throughput`](ThroughputSynchronized::peek_throughput), so
+/// collecting does not reset the window. Nothing is observed while the
+/// window has no rate. The gauge stays registered as long as the returned
+/// instrument is alive.
+pub fn register_observable_gauge<T, N>(
+    meter: &Meter,
+    name: N,
+    tp: Arc<ThroughputSynchronized<T>>,
+) -> ObservableGauge<f64>
+where
+    T: TimeSource + Send + 'static,
+    N: Into<Cow<'static, str>>,
+{
+    meter
+        .f64_observable_gauge(name)
+        .with_callback(move |observer| {
+            if let Some(rate) = tp.peek_throughput() {
+                observer.observe(rate, &[]);
+            }
+        })
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use ::opentelemetry::metrics::MeterProvider;
+    use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData};
+    use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+
+    use super::register_observable_gauge;
+    use crate::testing::ManualClock;
+    use crate::tp::{ThroughputSynchronized, TimeSource};
+
+    #[test]
+    fn test_observable_gauge() {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+
+        let clock = ManualClock::now();
+        let tp: Arc<ThroughputSynchronized<ManualClock>> = Arc::new(ThroughputSynchronized::new());
+        let _gauge = register_observable_gauge(&provider.meter("tp"), "rate", tp.clone());
+        tp.report(12345);
+        clock.advance(Duration::from_secs(10));
+
+        provider.force_flush().unwrap();
+
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let metric = metrics
+            .iter()
+            .flat_map(|resource| resource.scope_metrics())
+            .flat_map(|scope| scope.metrics())
+            .find(|metric| metric.name() == "rate")
+            .unwrap();
+        let value = match metric.data() {
+            AggregatedMetrics::F64(MetricData::Gauge(gauge)) => {
+                gauge.data_points().next().unwrap().value()
+            }
+            _ => panic!("unexpected metric data"),
+        };
+
+        assert_approx_eq!(value, 1234.5);
+        // collecting doesn't reset the window
+        assert_eq!(tp.count(), 12345);
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::Instant;
+
+use ::prometheus::core::{Collector, Desc};
+use ::prometheus::proto::MetricFamily;
+use ::prometheus::{Gauge, Opts};
+
+use crate::tp::{Throughput, ThroughputSynchronized, TimeSource};
+
+/// Prometheus collector exposing the rate of a [`ThroughputSynchronized`]
+/// as a gauge.
+///
+/// Every scrape reads the rate with
+/// [`peek_throughput`](ThroughputSynchronized::peek_throughput), so scraping
+/// does not reset the window. A window without a rate is exported as `0`.
+#[derive(Debug)]
+pub struct ThroughputGauge<T: TimeSource = Instant> {
+    tp: ThroughputSynchronized<T>,
+    gauge: Gauge,
+}
+
+impl<T: TimeSource> ThroughputGauge<T> {
+    pub fn new<S1: Into<String>, S2: Into<String>>(
+        name: S1,
+        help: S2,
+    ) -> ::prometheus::Result<Self> {
+        Ok(Self {
+            tp: ThroughputSynchronized::new(),
+            gauge: Gauge::with_opts(Opts::new(name, help))?,
+        })
+    }
+
+    /// Exposes a configured throughput, naming the gauge after the
+    /// [name](Throughput::name) of `tp`. Fails if `tp` has no name.
+    pub fn from_throughput<S: Into<String>>(
+        tp: Throughput<T>,
+        help: S,
+    ) -> ::prometheus::Result<Self> {
+        let name = tp
+            .name()
+            .ok_or_else(|| ::prometheus::Error::Msg("the throughput has no name".to_owned()))?
+            .to_owned();
+
+        Ok(Self {
+            gauge: Gauge::with_opts(Opts::new(name, help))?,
+            tp: tp.into(),
+        })
+    }
+
+    pub fn report(&self, value: u64) {
+        self.tp.report(value);
+    }
+
+    /// Returns the wrapped throughput, e.g. to reset it.
+    pub fn throughput(&self) -> &ThroughputSynchronized<T> {
+        &self.tp
+    }
+}
+
+impl<T: TimeSource + Send> Collector for ThroughputGauge<T> {
+    fn desc(&self) -> Vec<&Desc> {
+        self.gauge.desc()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        self.gauge.set(self.tp.peek_throughput().unwrap_or(0.0));
+        self.gauge.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::time::Duration;
+
+    use ::prometheus::core::Collector;
+    use ::prometheus::Registry;
+
+    use super::ThroughputGauge;
+    use crate::testing::ManualClock;
+    use crate::tp::{Throughput, TimeSource};
+
+    #[test]
+    fn test_collect() {
+        let clock = ManualClock::now();
+        let gauge: ThroughputGauge<ManualClock> =
+            ThroughputGauge::new("bytes_per_second", "Bytes processed per second").unwrap();
+        gauge.report(25);
+        clock.advance(Duration::from_secs(10));
+
+        let registry = Registry::new();
+        registry.register(Box::new(gauge)).unwrap();
+
+        for _ in 0..2 {
+            let families = registry.gather();
+            assert_eq!(families.len(), 1);
+            assert_eq!(families[0].name(), "bytes_per_second");
+            assert_eq!(families[0].help(), "Bytes processed per second");
+            assert_approx_eq!(families[0].get_metric()[0].get_gauge().get_value(), 2.5);
+        }
+    }
+
+    #[test]
+    fn test_from_throughput() {
+        let clock = ManualClock::now();
+        let gauge: ThroughputGauge<ManualClock> = ThroughputGauge::from_throughput(
+            Throughput::builder().name("uploads").build(),
+            "Uploads per second",
+        )
+        .unwrap();
+        gauge.report(5);
+        clock.advance(Duration::from_secs(10));
+
+        let families = Collector::collect(&gauge);
+        assert_eq!(families[0].name(), "uploads");
+        assert_approx_eq!(families[0].get_metric()[0].get_gauge().get_value(), 0.5);
+
+        let unnamed: Throughput<ManualClock> = Throughput::new();
+        assert!(ThroughputGauge::from_throughput(unnamed, "Uploads per second").is_err());
+    }
+}
@@ -0,0 +1,100 @@
+use alloc::vec::Vec;
+use core::option::Option;
+
+use crate::ring::RateRing;
+use crate::tp::{Accumulate, TimeSource};
+
+/// Quantiles of the window rates of a [`Throughput`](crate::Throughput).
+///
+/// Every [`tick`](RateQuantiles::tick) closes the current window and keeps
+/// its rate in a buffer of the last `capacity` rates. Quantiles are exact
+/// over these rates, but windows older than the buffer are forgotten
+/// entirely; no sampling takes place. Computing a quantile sorts a copy of
+/// the buffer, so it costs `O(capacity log capacity)`.
+#[derive(Debug)]
+pub struct RateQuantiles<T: TimeSource, V: Accumulate = u64> {
+    ring: RateRing<T, V>,
+}
+
+impl<T: TimeSource, V: Accumulate> RateQuantiles<T, V> {
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            ring: RateRing::new(capacity),
+        }
+    }
+
+    pub fn report(&mut self, value: V) {
+        self.ring.report(value);
+    }
+
+    /// Closes the current window and records its rate, evicting the oldest
+    /// rate if the buffer is full. A window without a rate is not recorded.
+    pub fn tick(&mut self) -> Option<f64> {
+        self.ring.tick()
+    }
+
+    /// Returns the `q`-quantile of the recorded rates using the nearest-rank
+    /// method, e.g. `quantile(0.95)` for p95. Returns `None` if no rate was
+    /// recorded yet or `q` is not within `0.0..=1.0`.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let rates = self.ring.rates();
+        if rates.is_empty() || !(0.0..=1.0).contains(&q) {
+            return None;
+        }
+
+        let mut sorted: Vec<f64> = rates.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+
+        // ceil without std
+        let exact = q * sorted.len() as f64;
+        let rank = exact as usize + usize::from((exact as usize as f64) < exact);
+        Some(sorted[rank.saturating_sub(1)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::time::Duration;
+
+    use super::RateQuantiles;
+    use crate::testing::ManualClock;
+    use crate::tp::TimeSource;
+
+    fn feed(quantiles: &mut RateQuantiles<ManualClock>, rates: impl Iterator<Item = u64>) {
+        let clock = ManualClock::now();
+        for rate in rates {
+            quantiles.report(rate);
+            clock.advance(Duration::from_secs(1));
+            quantiles.tick();
+        }
+    }
+
+    #[test]
+    fn test_quantiles() {
+        let mut quantiles: RateQuantiles<ManualClock> = RateQuantiles::new(100);
+        assert_eq!(None, quantiles.quantile(0.5));
+
+        // out of order, to make sure the rates get sorted
+        feed(&mut quantiles, (1..=100).rev());
+
+        assert_approx_eq!(quantiles.quantile(0.5).unwrap(), 50.0);
+        assert_approx_eq!(quantiles.quantile(0.95).unwrap(), 95.0);
+        assert_approx_eq!(quantiles.quantile(0.0).unwrap(), 1.0);
+        assert_approx_eq!(quantiles.quantile(1.0).unwrap(), 100.0);
+        assert_eq!(None, quantiles.quantile(1.5));
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut quantiles: RateQuantiles<ManualClock> = RateQuantiles::new(10);
+        feed(&mut quantiles, 1..=100);
+
+        assert_approx_eq!(quantiles.quantile(0.5).unwrap(), 95.0);
+        assert_approx_eq!(quantiles.quantile(0.0).unwrap(), 91.0);
+    }
+}
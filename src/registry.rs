@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use std::mem;
+use std::option::Option;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Instant;
+
+use crate::tp::{Snapshot, ThroughputSynchronized, TimeSource};
+
+/// Collection of named throughputs.
+///
+/// Entries are created lazily on the first [`report`](Self::report) for a
+/// name. Reporting to an existing entry only takes the registry's read lock,
+/// so producers of different metrics do not contend with each other.
+#[derive(Debug)]
+pub struct ThroughputRegistry<T: TimeSource = Instant> {
+    entries: RwLock<BTreeMap<String, ThroughputSynchronized<T>>>,
+}
+
+impl<T: TimeSource> ThroughputRegistry<T> {
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    // the map stays consistent if a user panics while holding the lock, so
+    // the registry keeps working after poisoning like the wrappers do
+    fn read(&self) -> RwLockReadGuard<'_, BTreeMap<String, ThroughputSynchronized<T>>> {
+        self.entries.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, BTreeMap<String, ThroughputSynchronized<T>>> {
+        self.entries.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Reports `value` to the throughput named `name`, creating it if it
+    /// doesn't exist yet.
+    pub fn report(&self, name: &str, value: u64) {
+        if let Some(tp) = self.read().get(name) {
+            tp.report(value);
+            return;
+        }
+
+        self.write()
+            .entry(name.to_owned())
+            .or_default()
+            .report(value);
+    }
+
+    /// Returns the rate of the throughput named `name` and starts a new
+    /// window for it, or `None` if nothing was reported under this name.
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput(&self, name: &str) -> Option<f64> {
+        self.read().get(name)?.throughput()
+    }
+
+    /// Returns the approximate memory used by the registry and its entries.
+    /// The overhead of the map's nodes is not included.
+    pub fn approx_size_bytes(&self) -> usize {
+        let entry = mem::size_of::<String>() + mem::size_of::<ThroughputSynchronized<T>>();
+
+        mem::size_of::<Self>()
+            + self
+                .read()
+                .keys()
+                .map(|name| entry + name.capacity())
+                .sum::<usize>()
+    }
+
+    /// Returns a snapshot of every entry, ordered by name, without
+    /// resetting them. The registry is read-locked once for the whole
+    /// sweep and each entry is locked once for its snapshot.
+    pub fn snapshot_all(&self) -> Vec<(String, Snapshot)> {
+        self.read()
+            .iter()
+            .map(|(name, tp)| (name.clone(), tp.snapshot()))
+            .collect()
+    }
+}
+
+impl<T: TimeSource> Default for ThroughputRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::panic;
+    use std::time::Duration;
+
+    use super::ThroughputRegistry;
+    use crate::testing::ManualClock;
+    use crate::tp::TimeSource;
+
+    #[test]
+    fn test_registry() {
+        let clock = ManualClock::now();
+        let registry: ThroughputRegistry<ManualClock> = ThroughputRegistry::new();
+        assert_eq!(registry.throughput("requests"), None);
+
+        registry.report("requests", 10);
+        registry.report("bytes", 1000);
+        registry.report("requests", 10);
+        clock.advance(Duration::from_secs(2));
+
+        let all = registry.snapshot_all();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].0, "bytes");
+        assert_approx_eq!(all[0].1.rate.unwrap(), 500.0);
+        assert_eq!(all[1].0, "requests");
+        assert_approx_eq!(all[1].1.rate.unwrap(), 10.0);
+
+        assert_approx_eq!(registry.throughput("requests").unwrap(), 10.0);
+        clock.advance(Duration::from_secs(2));
+        assert_approx_eq!(registry.throughput("requests").unwrap(), 0.0);
+        assert_approx_eq!(registry.throughput("bytes").unwrap(), 250.0);
+    }
+
+    #[test]
+    fn test_poisoned() {
+        let registry: ThroughputRegistry = ThroughputRegistry::new();
+        registry.report("requests", 1);
+
+        let _ = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            let _guard = registry.entries.write().unwrap();
+            panic!("poisoning the registry");
+        }));
+        assert!(registry.entries.is_poisoned());
+
+        registry.report("requests", 1);
+        registry.report("bytes", 1);
+        assert_eq!(registry.snapshot_all().len(), 2);
+        assert!(registry.throughput("requests").is_some());
+    }
+
+    #[test]
+    fn test_approx_size_bytes() {
+        let registry: ThroughputRegistry<ManualClock> = ThroughputRegistry::new();
+        let empty = registry.approx_size_bytes();
+
+        registry.report("a", 1);
+        let one = registry.approx_size_bytes();
+        assert!(one > empty);
+
+        registry.report("b", 1);
+        registry.report("a", 1);
+        assert_eq!(registry.approx_size_bytes() - one, one - empty);
+    }
+
+    #[test]
+    fn test_snapshot_all() {
+        let clock = ManualClock::now();
+        let registry: ThroughputRegistry<ManualClock> = ThroughputRegistry::new();
+        assert!(registry.snapshot_all().is_empty());
+
+        registry.report("requests", 3);
+        registry.report("bytes", 1000);
+        registry.report("errors", 1);
+        registry.report("bytes", 500);
+        clock.advance(Duration::from_secs(1));
+
+        let all = registry.snapshot_all();
+        let names: Vec<&str> = all.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["bytes", "errors", "requests"]);
+        let sums: Vec<u64> = all.iter().map(|(_, snapshot)| snapshot.sum).collect();
+        assert_eq!(sums, [1500, 1, 3]);
+        assert_eq!(all[0].1.events, 2);
+
+        // snapshots don't reset the entries
+        assert_approx_eq!(registry.throughput("bytes").unwrap(), 1500.0);
+    }
+}
@@ -0,0 +1,58 @@
+use alloc::collections::VecDeque;
+use core::mem;
+use core::option::Option;
+
+use crate::tp::{Accumulate, Throughput, TimeSource};
+
+// a throughput whose window rates are kept in a buffer of the last
+// `capacity` ones, shared by `TimeSeries` and `RateQuantiles`
+#[derive(Debug)]
+pub(crate) struct RateRing<T: TimeSource, V: Accumulate> {
+    tp: Throughput<T, V>,
+    rates: VecDeque<f64>,
+    capacity: usize,
+}
+
+impl<T: TimeSource, V: Accumulate> RateRing<T, V> {
+    // panics if `capacity` is zero
+    pub(crate) fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be non-zero");
+
+        Self {
+            tp: Throughput::new(),
+            rates: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub(crate) fn report(&mut self, value: V) {
+        self.tp.report(value);
+    }
+
+    // closes the current window and records its rate, evicting the oldest
+    // one if the buffer is full; a window without a rate is not recorded
+    pub(crate) fn tick(&mut self) -> Option<f64> {
+        let tp = self.tp.throughput();
+        if let Some(rate) = tp {
+            if self.rates.len() == self.capacity {
+                self.rates.pop_front();
+            }
+            self.rates.push_back(rate);
+        }
+
+        tp
+    }
+
+    // oldest first
+    pub(crate) fn rates(&self) -> &VecDeque<f64> {
+        &self.rates
+    }
+
+    pub(crate) fn make_contiguous(&mut self) {
+        self.rates.make_contiguous();
+    }
+
+    pub(crate) fn approx_size_bytes(&self) -> usize {
+        mem::size_of::<Self>() + self.rates.capacity() * mem::size_of::<f64>()
+    }
+}
@@ -0,0 +1,214 @@
+use std::mem;
+use std::option::Option;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::tp::TimeSource;
+
+// counter handing out shard indices to threads in the order they first report
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static SHARD: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed);
+}
+
+// one shard per cache line, so producers on different shards don't
+// invalidate each other's cache lines
+#[derive(Debug, Default)]
+#[repr(align(64))]
+struct Shard(AtomicU64);
+
+/// Throughput for many producers, with one accumulator per thread.
+///
+/// Each thread reports into its own shard, so in contrast to
+/// [`ThroughputAtomic`](crate::ThroughputAtomic) concurrent producers don't
+/// contend on a single atomic. Threads are assigned to the shards round
+/// robin; with more threads than shards some of them share a shard, which
+/// is still correct, just slower.
+///
+/// Every shard occupies a cache line of its own, so the memory usage is
+/// `64 * shards` bytes. [`throughput`](Self::throughput) takes the shards one
+/// after another, so a report racing with it is attributed to either the
+/// closed or the new window, but never lost. The sums saturate at
+/// `u64::MAX` like that of [`Throughput`](crate::Throughput), both per shard
+/// and over all shards.
+#[derive(Debug)]
+pub struct ShardedThroughput<T: TimeSource = Instant> {
+    initial_time: Mutex<T>,
+    shards: Box<[Shard]>,
+}
+
+impl<T: TimeSource> ShardedThroughput<T> {
+    /// # Panics
+    ///
+    /// Panics if `shards` is zero.
+    pub fn new(shards: usize) -> Self {
+        assert!(shards > 0, "shards must be non-zero");
+
+        Self {
+            initial_time: Mutex::new(T::now()),
+            shards: (0..shards).map(|_| Shard::default()).collect(),
+        }
+    }
+
+    fn initial_time(&self) -> MutexGuard<'_, T> {
+        self.initial_time
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn rate(sum: u64, elapsed: Duration) -> Option<f64> {
+        let denominator = elapsed.as_secs_f64();
+        if denominator == 0.0 {
+            None
+        } else {
+            Some(sum as f64 / denominator)
+        }
+    }
+
+    /// Returns the number of shards.
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns the approximate memory used, including the shards.
+    pub fn approx_size_bytes(&self) -> usize {
+        mem::size_of::<Self>() + self.shards.len() * mem::size_of::<Shard>()
+    }
+
+    /// Adds `value` to the shard of the calling thread.
+    pub fn report(&self, value: u64) {
+        let index = SHARD.with(|shard| *shard) % self.shards.len();
+        // the closure never fails, so neither does the update
+        let _ = self.shards[index]
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |sum| {
+                Some(sum.saturating_add(value))
+            });
+    }
+
+    /// Returns the sum reported in the current window over all shards.
+    pub fn count(&self) -> u64 {
+        self.shards.iter().fold(0, |sum, shard| {
+            sum.saturating_add(shard.0.load(Ordering::Acquire))
+        })
+    }
+
+    /// Returns how long the current window has been running.
+    pub fn elapsed(&self) -> Duration {
+        self.initial_time().elapsed()
+    }
+
+    /// Returns the rate of the current window without resetting it.
+    #[must_use]
+    pub fn peek_throughput(&self) -> Option<f64> {
+        let initial_time = self.initial_time();
+        Self::rate(self.count(), initial_time.elapsed())
+    }
+
+    /// Returns the rate of the current window and starts a new one.
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput(&self) -> Option<f64> {
+        let mut initial_time = self.initial_time();
+        let elapsed = initial_time.elapsed();
+        *initial_time = initial_time.renew();
+
+        let sum = self.shards.iter().fold(0, |sum: u64, shard| {
+            sum.saturating_add(shard.0.swap(0, Ordering::AcqRel))
+        });
+
+        Self::rate(sum, elapsed)
+    }
+}
+
+impl<T: TimeSource> Default for ShardedThroughput<T> {
+    /// Creates one shard per available CPU.
+    fn default() -> Self {
+        Self::new(std::thread::available_parallelism().map_or(1, usize::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::ShardedThroughput;
+    use crate::testing::ManualClock;
+    use crate::tp::TimeSource;
+
+    #[test]
+    fn test_throughput() {
+        let clock = ManualClock::now();
+        let tp: ShardedThroughput<ManualClock> = ShardedThroughput::new(4);
+        tp.report(10);
+        tp.report(10);
+        assert_eq!(tp.count(), 20);
+
+        clock.advance(Duration::from_secs(2));
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 10.0);
+        assert_approx_eq!(tp.throughput().unwrap(), 10.0);
+        assert_eq!(tp.count(), 0);
+    }
+
+    #[test]
+    fn test_saturates() {
+        let tp: Arc<ShardedThroughput> = Arc::new(ShardedThroughput::new(2));
+        // whether both threads share a shard or not, the sum saturates
+        let producers: Vec<_> = (0..2)
+            .map(|_| {
+                let tp = tp.clone();
+                thread::spawn(move || tp.report(u64::MAX / 2 + 1))
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        assert_eq!(tp.count(), u64::MAX);
+    }
+
+    #[test]
+    fn test_producers() {
+        const THREADS: u64 = 8;
+        const REPORTS: u64 = 100_000;
+
+        // fewer shards than threads, so some of them share a shard
+        let tp: Arc<ShardedThroughput> = Arc::new(ShardedThroughput::new(3));
+        let producers: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let tp = tp.clone();
+                thread::spawn(move || {
+                    for _ in 0..REPORTS {
+                        tp.report(2);
+                    }
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        assert_eq!(tp.count(), THREADS * REPORTS * 2);
+    }
+
+    #[test]
+    fn test_approx_size_bytes() {
+        let one: ShardedThroughput = ShardedThroughput::new(1);
+        let many: ShardedThroughput = ShardedThroughput::new(16);
+
+        assert_eq!(many.approx_size_bytes() - one.approx_size_bytes(), 15 * 64);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_shards() {
+        let _: ShardedThroughput = ShardedThroughput::new(0);
+    }
+}
@@ -0,0 +1,72 @@
+use core::option::Option;
+
+use crate::aggregate::Aggregator;
+use crate::tp::{Accumulate, Throughput, TimeSource};
+
+/// Statsd client the rate is sent to, see [`Throughput::emit_statsd`].
+///
+/// Implement it for the client in use, e.g. by forwarding to `cadence`'s
+/// `Gauged::gauge`.
+pub trait StatsdSink {
+    /// Sends `value` as the gauge `metric`.
+    fn gauge(&self, metric: &str, value: f64);
+}
+
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> Throughput<T, V, A> {
+    /// Computes the rate of the current window, starts a new one and sends
+    /// the rate as the gauge `metric` to `client`. Nothing is sent if the
+    /// window has no rate.
+    pub fn emit_statsd(&mut self, client: &impl StatsdSink, metric: &str) -> Option<f64> {
+        let tp = self.throughput();
+        if let Some(rate) = tp {
+            client.gauge(metric, rate);
+        }
+
+        tp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use super::StatsdSink;
+    use crate::testing::ManualClock;
+    use crate::tp::{Throughput, TimeSource};
+
+    #[derive(Default)]
+    struct CapturingSink {
+        gauges: Mutex<Vec<(String, f64)>>,
+    }
+
+    impl StatsdSink for CapturingSink {
+        fn gauge(&self, metric: &str, value: f64) {
+            self.gauges.lock().unwrap().push((metric.to_owned(), value));
+        }
+    }
+
+    #[test]
+    fn test_emit_statsd() {
+        let sink = CapturingSink::default();
+
+        let clock = ManualClock::now();
+        let mut tp: Throughput<ManualClock> = Throughput::new();
+        tp.report(12345);
+        clock.advance(Duration::from_secs(10));
+        assert_approx_eq!(tp.emit_statsd(&sink, "tp.rate").unwrap(), 1234.5);
+        assert_eq!(tp.count(), 0);
+
+        // no time elapsed in this window
+        let mut tp: Throughput<ManualClock> = Throughput::new();
+        tp.report(1);
+        assert_eq!(tp.emit_statsd(&sink, "tp.rate"), None);
+
+        let gauges = sink.gauges.lock().unwrap();
+        assert_eq!(gauges.len(), 1);
+        assert_eq!(gauges[0].0, "tp.rate");
+        assert_approx_eq!(gauges[0].1, 1234.5);
+    }
+}
@@ -0,0 +1,81 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+
+use crate::tp::{ThroughputSynchronized, TimeSource};
+
+/// Stream adapter reporting `1` for every item yielded by the wrapped
+/// stream, see [`ThroughputStreamExt::throughput`].
+#[derive(Debug)]
+pub struct ThroughputStream<S, T: TimeSource> {
+    inner: S,
+    tp: Arc<ThroughputSynchronized<T>>,
+}
+
+impl<S: Stream + Unpin, T: TimeSource> ThroughputStream<S, T> {
+    pub fn new(inner: S, tp: Arc<ThroughputSynchronized<T>>) -> Self {
+        Self { inner, tp }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Stream + Unpin, T: TimeSource> Stream for ThroughputStream<S, T> {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let poll = Pin::new(&mut self.inner).poll_next(cx);
+        if let Poll::Ready(Some(_)) = poll {
+            self.tp.report(1);
+        }
+
+        poll
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+pub trait ThroughputStreamExt: Stream + Unpin + Sized {
+    /// Reports every item yielded by this stream to `tp`.
+    fn throughput<T: TimeSource>(
+        self,
+        tp: Arc<ThroughputSynchronized<T>>,
+    ) -> ThroughputStream<Self, T> {
+        ThroughputStream::new(self, tp)
+    }
+}
+
+impl<S: Stream + Unpin> ThroughputStreamExt for S {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::executor::block_on;
+    use futures::stream::{self, StreamExt};
+
+    use super::ThroughputStreamExt;
+    use crate::testing::ManualClock;
+    use crate::tp::ThroughputSynchronized;
+
+    #[test]
+    fn test_stream() {
+        let tp: Arc<ThroughputSynchronized<ManualClock>> = Arc::new(ThroughputSynchronized::new());
+
+        let items: Vec<_> = block_on(
+            stream::iter(vec!["a", "b", "c"])
+                .throughput(tp.clone())
+                .collect(),
+        );
+
+        assert_eq!(items, vec!["a", "b", "c"]);
+        assert_eq!(tp.count(), 3);
+        assert_eq!(tp.event_count(), 3);
+    }
+}
@@ -0,0 +1,266 @@
+//! Deterministic time sources for tests.
+
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use crate::tp::TimeSource;
+
+thread_local! {
+    static DEFAULT: ManualClock = ManualClock::new();
+}
+
+/// Manually advanced [`TimeSource`].
+///
+/// A `ManualClock` is a handle to a shared "now", which only moves when
+/// [`advance`](ManualClock::advance) is called, which makes rates exactly
+/// predictable without sleeping. Clones and [renewed](TimeSource::renew)
+/// values read the same clock, also from other threads, while
+/// [`new`](ManualClock::new) creates an independent one.
+///
+/// [`now`](TimeSource::now), which types like [`Throughput`] use to start
+/// measuring, reads a default clock of the calling thread, so
+/// concurrently running tests do not affect each other. Use
+/// [`Throughput::starting_at`] to measure with a clock of its own.
+///
+/// [`Throughput`]: crate::Throughput
+/// [`Throughput::starting_at`]: crate::Throughput::starting_at
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<Duration>>,
+    at: Duration,
+    // time the reading lies before `at`, for readings back-dated before the
+    // start of the clock
+    before: Duration,
+}
+
+impl ManualClock {
+    /// Creates an independent clock standing at zero.
+    pub fn new() -> Self {
+        ManualClock {
+            now: Arc::new(Mutex::new(Duration::default())),
+            at: Duration::default(),
+            before: Duration::default(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Duration> {
+        self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Moves "now" of the clock forward by `d`, for all values reading it.
+    pub fn advance(&self, d: Duration) {
+        let mut now = self.lock();
+        *now += d;
+    }
+
+    /// Returns how far the clock has been advanced.
+    pub fn current(&self) -> Duration {
+        *self.lock()
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSource for ManualClock {
+    /// Reads the default clock of the calling thread.
+    fn now() -> Self {
+        DEFAULT.with(ManualClock::renew)
+    }
+
+    /// Like [`now`](TimeSource::now), but back-dated by `d`, also beyond
+    /// the start of the clock.
+    fn now_minus(d: Duration) -> Self {
+        ManualClock {
+            before: d,
+            ..Self::now()
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.current().saturating_sub(self.at) + self.before
+    }
+
+    fn duration_since(&self, earlier: &Self) -> Duration {
+        (self.at + earlier.before).saturating_sub(earlier.at + self.before)
+    }
+
+    fn renew(&self) -> Self {
+        ManualClock {
+            now: self.now.clone(),
+            at: self.current(),
+            before: Duration::default(),
+        }
+    }
+}
+
+/// [`TimeSource`] reporting the elapsed time returned by a closure, see
+/// [`Throughput::with_fn`](crate::Throughput::with_fn).
+///
+/// Values created by [`now`](TimeSource::now) have no closure and always
+/// report zero, and those created by [`now_minus`](TimeSource::now_minus)
+/// report the given offset on top. [`renew`](TimeSource::renew) keeps
+/// the closure, so new windows of a throughput keep using it.
+#[derive(Clone)]
+pub struct FnTimeSource {
+    elapsed: Option<Arc<dyn Fn() -> Duration + Send + Sync>>,
+    // added to the elapsed time, for back-dated values
+    offset: Duration,
+}
+
+impl FnTimeSource {
+    pub fn new<F: Fn() -> Duration + Send + Sync + 'static>(elapsed: F) -> Self {
+        FnTimeSource {
+            elapsed: Some(Arc::new(elapsed)),
+            offset: Duration::default(),
+        }
+    }
+}
+
+impl fmt::Debug for FnTimeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FnTimeSource")
+            .field("elapsed", &self.elapsed())
+            .finish()
+    }
+}
+
+impl TimeSource for FnTimeSource {
+    fn now() -> Self {
+        FnTimeSource {
+            elapsed: None,
+            offset: Duration::default(),
+        }
+    }
+
+    fn now_minus(d: Duration) -> Self {
+        FnTimeSource {
+            offset: d,
+            ..Self::now()
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.elapsed.as_ref().map_or(Duration::default(), |f| f()) + self.offset
+    }
+
+    fn renew(&self) -> Self {
+        FnTimeSource {
+            elapsed: self.elapsed.clone(),
+            offset: Duration::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{FnTimeSource, ManualClock};
+    use crate::tp::TimeSource;
+
+    #[test]
+    fn test_advance() {
+        let clock = ManualClock::now();
+        assert_eq!(clock.elapsed(), Duration::default());
+
+        clock.advance(Duration::from_millis(1500));
+        let later = ManualClock::now();
+        clock.advance(Duration::from_secs(1));
+
+        assert_eq!(clock.elapsed(), Duration::from_millis(2500));
+        assert_eq!(later.elapsed(), Duration::from_secs(1));
+        assert_eq!(clock.current(), Duration::from_millis(2500));
+    }
+
+    #[test]
+    fn test_independent_clocks() {
+        let first = ManualClock::new();
+        let second = ManualClock::new();
+        first.advance(Duration::from_secs(2));
+
+        assert_eq!(first.elapsed(), Duration::from_secs(2));
+        assert_eq!(second.elapsed(), Duration::default());
+        assert_eq!(ManualClock::now().elapsed(), Duration::default());
+
+        let mut tp: crate::Throughput<ManualClock> = crate::Throughput::starting_at(second.clone());
+        tp.report(30);
+        first.advance(Duration::from_secs(1));
+        second.advance(Duration::from_secs(3));
+        assert_approx_eq!(tp.throughput().unwrap(), 10.0);
+    }
+
+    #[test]
+    fn test_across_threads() {
+        let clock = ManualClock::now();
+        let tp: Arc<crate::ThroughputSynchronized<ManualClock>> =
+            Arc::new(crate::ThroughputSynchronized::new());
+        tp.report(20);
+
+        let reader = {
+            let tp = tp.clone();
+            let clock = clock.clone();
+            thread::spawn(move || {
+                clock.advance(Duration::from_secs(2));
+                tp.throughput()
+            })
+        };
+
+        assert_approx_eq!(reader.join().unwrap().unwrap(), 10.0);
+        assert_eq!(clock.current(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_fn_time_source() {
+        let mut tp = crate::Throughput::<FnTimeSource>::with_fn(|| Duration::from_secs(2));
+        tp.report(10);
+        assert_eq!(tp.elapsed(), Duration::from_secs(2));
+        assert_approx_eq!(tp.throughput().unwrap(), 5.0);
+
+        // the closure survives the reset
+        tp.report(3);
+        assert_approx_eq!(tp.throughput().unwrap(), 1.5);
+
+        assert_eq!(FnTimeSource::now().elapsed(), Duration::default());
+        let back_dated = FnTimeSource::now_minus(Duration::from_secs(3));
+        assert_eq!(back_dated.elapsed(), Duration::from_secs(3));
+        assert_eq!(back_dated.renew().elapsed(), Duration::default());
+    }
+
+    #[test]
+    fn test_now_minus() {
+        ManualClock::now().advance(Duration::from_secs(5));
+        let clock = ManualClock::now_minus(Duration::from_secs(2));
+        assert_eq!(clock.elapsed(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_duration_since() {
+        let earlier = ManualClock::now();
+        earlier.advance(Duration::from_secs(3));
+        let later = ManualClock::now();
+
+        assert_eq!(later.duration_since(&earlier), Duration::from_secs(3));
+        assert_eq!(earlier.duration_since(&later), Duration::default());
+    }
+
+    #[test]
+    fn test_now_minus_before_start() {
+        let clock = ManualClock::now_minus(Duration::from_secs(1));
+        assert_eq!(clock.elapsed(), Duration::from_secs(1));
+
+        clock.advance(Duration::from_secs(2));
+        let later = ManualClock::now();
+        assert_eq!(clock.elapsed(), Duration::from_secs(3));
+        assert_eq!(later.duration_since(&clock), Duration::from_secs(3));
+        assert_eq!(clock.duration_since(&later), Duration::default());
+    }
+}
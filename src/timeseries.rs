@@ -0,0 +1,98 @@
+use core::option::Option;
+
+use crate::ring::RateRing;
+use crate::tp::{Accumulate, TimeSource};
+
+/// The window rates of a [`Throughput`](crate::Throughput) over time, e.g.
+/// for a sparkline.
+///
+/// Every [`tick`](TimeSeries::tick) closes the current window and appends
+/// its rate to a buffer of the last `capacity` rates, dropping the oldest
+/// one once the buffer is full. Call it on a fixed schedule, e.g. once per
+/// second, to get evenly spaced samples.
+#[derive(Debug)]
+pub struct TimeSeries<T: TimeSource, V: Accumulate = u64> {
+    ring: RateRing<T, V>,
+}
+
+impl<T: TimeSource, V: Accumulate> TimeSeries<T, V> {
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            ring: RateRing::new(capacity),
+        }
+    }
+
+    pub fn report(&mut self, value: V) {
+        self.ring.report(value);
+    }
+
+    /// Closes the current window and appends its rate to the history. A
+    /// window without a rate is not recorded.
+    pub fn tick(&mut self) -> Option<f64> {
+        let tp = self.ring.tick();
+        // keeps `history` a single slice, at the cost of moving up to
+        // `capacity` rates
+        self.ring.make_contiguous();
+
+        tp
+    }
+
+    /// Returns the recorded rates, oldest first.
+    pub fn history(&self) -> &[f64] {
+        self.ring.rates().as_slices().0
+    }
+
+    /// Returns the approximate memory used, including the rate buffer.
+    pub fn approx_size_bytes(&self) -> usize {
+        // the ring is the only field
+        self.ring.approx_size_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::TimeSeries;
+    use crate::testing::ManualClock;
+    use crate::tp::TimeSource;
+
+    #[test]
+    fn test_history() {
+        let clock = ManualClock::now();
+        let mut series: TimeSeries<ManualClock> = TimeSeries::new(3);
+        assert!(series.history().is_empty());
+
+        for value in 1..=5 {
+            series.report(value);
+            clock.advance(Duration::from_secs(1));
+            series.tick();
+
+            assert!(series.history().len() <= 3);
+        }
+
+        assert_eq!(series.history(), &[3.0, 4.0, 5.0]);
+
+        // a tick without elapsed time has no rate
+        assert_eq!(series.tick(), None);
+        assert_eq!(series.history(), &[3.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn test_approx_size_bytes() {
+        let small: TimeSeries<ManualClock> = TimeSeries::new(10);
+        let large: TimeSeries<ManualClock> = TimeSeries::new(1000);
+
+        assert!(small.approx_size_bytes() >= 10 * 8);
+        assert!(large.approx_size_bytes() >= small.approx_size_bytes() + 990 * 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_capacity() {
+        let _: TimeSeries<ManualClock> = TimeSeries::new(0);
+    }
+}
@@ -1,184 +1,3400 @@
-use std::option::Option;
-use std::sync::Mutex;
-use std::time::{Duration, Instant};
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::sync::Arc;
+use core::fmt;
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+use core::ops::AddAssign;
+#[cfg(feature = "std")]
+use core::ops::Deref;
+use core::option::Option;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::error::Error;
+#[cfg(feature = "std")]
+use std::sync::{
+    Mutex, MutexGuard, OnceLock, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    TryLockError,
+};
+#[cfg(feature = "std")]
+use std::time::Instant;
 
+use crate::aggregate::{Aggregator, SumPerSecond};
+#[cfg(feature = "alloc")]
+use crate::histogram::ValueHistogram;
+
+/// Clock a [`Throughput`] measures elapsed time with.
+///
+/// Monotonic clocks like [`Instant`] are recommended. Wall clocks like
+/// `SystemTime` can jump backwards, e.g. on NTP adjustments, and should
+/// report zero elapsed time then, which makes the rate `None` rather than
+/// absurd.
 pub trait TimeSource {
     fn now() -> Self;
+
+    /// Returns the time `d` before now, e.g. to back-date a window. Sources
+    /// which cannot go back that far may return an earlier limit instead.
+    /// The default cannot back-date at all and returns now, so override it
+    /// for sources which can.
+    fn now_minus(_d: Duration) -> Self
+    where
+        Self: Sized,
+    {
+        Self::now()
+    }
+
     fn elapsed(&self) -> Duration;
+
+    /// Returns the time from `earlier` to `self`, or zero if `earlier` is
+    /// later. The default compares the elapsed times of both, sources which
+    /// can subtract directly should override it.
+    fn duration_since(&self, earlier: &Self) -> Duration {
+        earlier.elapsed().saturating_sub(self.elapsed())
+    }
+
+    /// Returns now, for sources which carry state like
+    /// [`FnTimeSource`](crate::testing::FnTimeSource) derived from `self`.
+    /// [`Throughput`] uses this to start new windows.
+    fn renew(&self) -> Self
+    where
+        Self: Sized,
+    {
+        Self::now()
+    }
 }
 
+// number of ticks the clock resolution is sampled over, and how often the
+// clock is read per tick before giving up on it advancing
+const RESOLUTION_SAMPLES: usize = 3;
+const RESOLUTION_SPINS: usize = 1_000_000;
+
+// smallest non-zero step between readings of `T`, or zero if it never
+// advanced within the spin budget
+fn sample_resolution<T: TimeSource>() -> Duration {
+    (0..RESOLUTION_SAMPLES)
+        .filter_map(|_| {
+            let start = T::now();
+            (0..RESOLUTION_SPINS)
+                .map(|_| start.elapsed())
+                .find(|step| !step.is_zero())
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "std")]
 impl TimeSource for Instant {
     fn now() -> Self {
         Instant::now()
     }
 
+    /// Falls back to now if the platform cannot represent the result, e.g.
+    /// shortly after boot.
+    fn now_minus(d: Duration) -> Self {
+        let now = Instant::now();
+        now.checked_sub(d).unwrap_or(now)
+    }
+
     fn elapsed(&self) -> Duration {
         Instant::elapsed(self)
     }
+
+    fn duration_since(&self, earlier: &Self) -> Duration {
+        self.saturating_duration_since(*earlier)
+    }
+}
+
+/// Value type which can be accumulated by a [`Throughput`].
+pub trait Accumulate: Copy {
+    fn zero() -> Self;
+    /// Adds `value`, saturating at the numeric bounds instead of overflowing.
+    fn accumulate(self, value: Self) -> Self;
+    /// Adds `value`, returning `None` if the result would overflow.
+    fn checked_accumulate(self, value: Self) -> Option<Self>;
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_accumulate_int {
+    ($($t:ty),*) => {
+        $(
+            impl Accumulate for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn accumulate(self, value: Self) -> Self {
+                    self.saturating_add(value)
+                }
+
+                fn checked_accumulate(self, value: Self) -> Option<Self> {
+                    self.checked_add(value)
+                }
+
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_accumulate_int!(u32, u64, u128, i64);
+
+impl Accumulate for f64 {
+    fn zero() -> Self {
+        0.0
+    }
+
+    fn accumulate(self, value: Self) -> Self {
+        self + value
+    }
+
+    fn checked_accumulate(self, value: Self) -> Option<Self> {
+        let sum = self + value;
+        if sum.is_finite() {
+            Some(sum)
+        } else {
+            None
+        }
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+/// Error returned by `checked_report` when the accumulated sum would
+/// overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverflowError;
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("throughput sum overflowed")
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for OverflowError {}
+
+/// Error returned by [`Throughput::try_throughput`] when no rate can be
+/// given for a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThroughputError {
+    /// No time elapsed in the window.
+    ZeroElapsed,
+    /// Nothing was reported in the window.
+    NoData,
+    /// The window has not run for the
+    /// [minimum elapsed time](ThroughputBuilder::min_elapsed) yet.
+    BelowMinElapsed,
+}
+
+impl fmt::Display for ThroughputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThroughputError::ZeroElapsed => f.write_str("no time elapsed in the window"),
+            ThroughputError::NoData => f.write_str("nothing reported in the window"),
+            ThroughputError::BelowMinElapsed => {
+                f.write_str("the window has not run for the minimum elapsed time")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ThroughputError {}
+
+/// Time unit a rate is expressed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RateUnit {
+    PerSecond,
+    PerMinute,
+    PerHour,
+}
+
+impl RateUnit {
+    /// Returns the length of the unit in seconds.
+    pub fn seconds(self) -> f64 {
+        match self {
+            RateUnit::PerSecond => 1.0,
+            RateUnit::PerMinute => 60.0,
+            RateUnit::PerHour => 3600.0,
+        }
+    }
+}
+
+/// Bound for the alarm set by [`Throughput::set_threshold`].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Threshold {
+    Below(f64),
+    Above(f64),
+}
+
+#[cfg(feature = "alloc")]
+impl Threshold {
+    fn is_crossed_by(self, rate: f64) -> bool {
+        match self {
+            Threshold::Below(bound) => rate < bound,
+            Threshold::Above(bound) => rate > bound,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+// shared, so that clones of a `Throughput` keep the alarm
+type ThresholdCallback = Arc<dyn Fn(f64) + Send + Sync>;
+
+// marks the throughput a wrapper creates without holding one
+pub(crate) type ThroughputMarker<T, V, A> = PhantomData<fn() -> Throughput<T, V, A>>;
+
+/// Number of buckets of the histogram enabled by
+/// [`Throughput::record_histogram`], enough to cover all `u64` values.
+pub const HISTOGRAM_BUCKETS: usize = 65;
+
+/// State of a window as returned by [`Throughput::snapshot`] and
+/// [`Throughput::peek_snapshot`].
+///
+/// The `Display` implementation prints e.g. `1234.5 units/s over 2.00s`, or
+/// `n/a` in place of the rate if no time elapsed.
+///
+/// Equality compares `sum`, `events` and `elapsed` only, as the rate is
+/// derived from them; use [`approx_eq`](Snapshot::approx_eq) to also compare
+/// the rates.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot<V = u64> {
+    pub rate: Option<f64>,
+    pub sum: V,
+    pub events: u64,
+    pub elapsed: Duration,
+}
+
+impl<V: PartialEq> Snapshot<V> {
+    /// Returns whether both snapshots are equal and their rates differ by at
+    /// most `epsilon`. Two missing rates are considered equal.
+    pub fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        let rates_eq = match (self.rate, other.rate) {
+            (Some(a), Some(b)) => a - b <= epsilon && b - a <= epsilon,
+            (None, None) => true,
+            _ => false,
+        };
+
+        rates_eq && self == other
+    }
+}
+
+impl<V: PartialEq> PartialEq for Snapshot<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.sum == other.sum && self.events == other.events && self.elapsed == other.elapsed
+    }
+}
+
+impl<V> fmt::Display for Snapshot<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rate {
+            Some(rate) => write!(f, "{:.1} units/s", rate)?,
+            None => f.write_str("n/a")?,
+        }
+        write!(f, " over {:.2}s", self.elapsed.as_secs_f64())
+    }
+}
+
+/// Plain summary of a window for sending over the wire, with the elapsed
+/// time in milliseconds.
+///
+/// Converting from `&mut Throughput` closes the window like
+/// [`Throughput::snapshot`]; use [`from_peek`](ThroughputReport::from_peek)
+/// to keep it running. The rate is per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThroughputReport<V = u64> {
+    pub rate: Option<f64>,
+    pub sum: V,
+    pub elapsed_ms: u128,
+}
+
+impl<V: Accumulate> ThroughputReport<V> {
+    /// Summarizes the current window of `tp` without resetting it.
+    pub fn from_peek<T: TimeSource, A: Aggregator<V>>(tp: &Throughput<T, V, A>) -> Self {
+        tp.peek_snapshot().into()
+    }
+}
+
+impl<V> From<Snapshot<V>> for ThroughputReport<V> {
+    fn from(snapshot: Snapshot<V>) -> Self {
+        Self {
+            rate: snapshot.rate,
+            sum: snapshot.sum,
+            elapsed_ms: snapshot.elapsed.as_millis(),
+        }
+    }
+}
+
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> From<&mut Throughput<T, V, A>>
+    for ThroughputReport<V>
+{
+    fn from(tp: &mut Throughput<T, V, A>) -> Self {
+        tp.snapshot().into()
+    }
+}
+
+/// Persistable state of a [`Throughput`] window, see
+/// [`Throughput::to_state`] and [`Throughput::from_state`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThroughputState<V = u64> {
+    pub sum: V,
+    pub events: u64,
+    pub elapsed: Duration,
 }
 
-#[derive(Debug)]
+/// Throughput of the values reported within the current window.
+///
+/// The rate is the sum of the values per second unless the [`Aggregator`]
+/// `A` combines them otherwise, e.g. [`MaxValue`](crate::MaxValue) for the
+/// largest value per window. The unit and scale apply to its figure like to
+/// a rate.
+///
+/// Cloning copies the current window, e.g. to compare it with a later
+/// state; a threshold callback is shared between the clones.
+#[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct Throughput<T: TimeSource> {
+pub struct Throughput<T: TimeSource, V: Accumulate = u64, A: Aggregator<V> = SumPerSecond> {
     initial_time: T,
-    sum: u32,
+    // start of the current pause, if any
+    paused_since: Option<T>,
+    // time spent in completed pauses of the current window
+    paused_duration: Duration,
+    sum: V,
+    // the values folded by the aggregator `A`, which yields the rate
+    acc: A::Acc,
+    events: u64,
+    // latencies passed to `report_with_latency` in the current window
+    latency_sum: Duration,
+    latency_events: u64,
+    // sum of all windows, only cleared by `reset_total`
+    lifetime_sum: V,
+    // time of the previous `report_instant`, kept across resets
+    last_report_time: Option<T>,
+    // time of the latest report of any kind, kept across resets
+    last_activity: Option<T>,
+    peak: Option<f64>,
+    min: Option<f64>,
+    // rate of the window last closed by `auto_reset`
+    completed_window: Option<f64>,
+    // boxed, so it only takes up space when enabled
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    histogram: Option<Box<ValueHistogram<HISTOGRAM_BUCKETS>>>,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    options: Options,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _aggregator: PhantomData<fn() -> A>,
+}
+
+// configuration of a `Throughput` which is kept across windows, grouped so
+// that `into_time_source` and the builder cannot miss a field
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Options {
+    // unit of the rates returned by `throughput` and `peek_throughput`
+    unit: RateUnit,
+    // whether `throughput` records the peak
+    track_peak: bool,
+    // whether `throughput` discards the current window as warmup
+    skip_window: bool,
+    // period after which a report closes the current window
+    auto_reset: Option<Duration>,
+    // time a window must have elapsed to have a rate
+    min_elapsed: Duration,
+    // factor the rates are multiplied by
+    scale: f64,
+    // label for `Display` and the integrations
+    #[cfg(feature = "alloc")]
+    name: Option<String>,
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    threshold: Option<(Threshold, ThresholdCallback)>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            unit: RateUnit::PerSecond,
+            track_peak: false,
+            skip_window: false,
+            auto_reset: None,
+            min_elapsed: Duration::default(),
+            scale: 1.0,
+            #[cfg(feature = "alloc")]
+            name: None,
+            #[cfg(feature = "alloc")]
+            threshold: None,
+        }
+    }
 }
 
-impl<T: TimeSource> Throughput<T> {
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> Throughput<T, V, A> {
     pub fn new() -> Self {
         Self {
-            sum: 0,
+            sum: V::zero(),
+            acc: A::zero(),
+            events: 0,
+            latency_sum: Duration::default(),
+            latency_events: 0,
             initial_time: T::now(),
+            paused_since: None,
+            paused_duration: Duration::default(),
+            lifetime_sum: V::zero(),
+            last_report_time: None,
+            last_activity: None,
+            peak: None,
+            min: None,
+            completed_window: None,
+            #[cfg(feature = "alloc")]
+            histogram: None,
+            options: Options::default(),
+            _aggregator: PhantomData,
+        }
+    }
+
+    /// Creates a throughput whose first window starts at `start` rather
+    /// than at [`T::now`](TimeSource::now). Later windows start at
+    /// [renewed](TimeSource::renew) values of `start`, e.g. readings of the
+    /// same [`ManualClock`](crate::testing::ManualClock).
+    pub fn starting_at(start: T) -> Self {
+        Self {
+            initial_time: start,
+            ..Self::new()
+        }
+    }
+
+    /// Returns a builder for a configured instance; `new()` is equivalent
+    /// to `builder().build()`.
+    pub fn builder() -> ThroughputBuilder<T, V, A> {
+        ThroughputBuilder::new()
+    }
+
+    /// Adds `value` to the current window. The sum saturates at the
+    /// maximum of `V` rather than overflowing.
+    pub fn report(&mut self, value: V) {
+        self.before_report();
+        self.sum = self.sum.accumulate(value);
+        self.acc = A::fold(self.acc, value);
+        self.lifetime_sum = self.lifetime_sum.accumulate(value);
+        self.events = self.events.saturating_add(1);
+        self.record(value);
+    }
+
+    fn before_report(&mut self) {
+        self.last_activity = Some(self.initial_time.renew());
+        if let Some(period) = self.options.auto_reset {
+            if self.elapsed() >= period {
+                self.completed_window = self.throughput();
+            }
+        }
+    }
+
+    /// Returns the rate of the window last closed by a report after the
+    /// [`auto_reset`](ThroughputBuilder::auto_reset) period, or `None` if
+    /// none was closed yet or it had no rate.
+    pub fn completed_window(&self) -> Option<f64> {
+        self.completed_window
+    }
+
+    fn record(&mut self, _value: V) {
+        #[cfg(feature = "alloc")]
+        if let Some(histogram) = &mut self.histogram {
+            histogram.record(_value.to_f64());
         }
     }
 
-    pub fn report(&mut self, value: u32) {
-        self.sum += value;
+    /// Starts bucketing every reported value into a [`ValueHistogram`],
+    /// which is cleared along with the window. Does nothing if already
+    /// recording. The histogram is allocated on first use.
+    #[cfg(feature = "alloc")]
+    pub fn record_histogram(&mut self) {
+        self.histogram
+            .get_or_insert_with(|| Box::new(ValueHistogram::new()));
+    }
+
+    /// Returns the distribution of values reported in the current window,
+    /// or `None` if not enabled with
+    /// [`record_histogram`](Self::record_histogram).
+    #[cfg(feature = "alloc")]
+    pub fn histogram(&self) -> Option<&ValueHistogram<HISTOGRAM_BUCKETS>> {
+        self.histogram.as_deref()
+    }
+
+    /// Reports `value` and additionally records the `latency` of the
+    /// operation it stands for, see
+    /// [`average_latency`](Self::average_latency).
+    pub fn report_with_latency(&mut self, value: V, latency: Duration) {
+        self.report(value);
+        self.latency_sum = self.latency_sum.saturating_add(latency);
+        self.latency_events = self.latency_events.saturating_add(1);
+    }
+
+    /// Adds all `values` to the current window in a single accumulation.
+    /// Every value counts as one report event.
+    pub fn report_many(&mut self, values: &[V]) {
+        self.report_iter(values.iter().copied());
+    }
+
+    /// Like [`report_many`](Self::report_many), but takes any iterator.
+    pub fn report_iter<I: IntoIterator<Item = V>>(&mut self, values: I) {
+        self.before_report();
+        #[cfg(feature = "alloc")]
+        let histogram = &mut self.histogram;
+        let (sum, acc, events) =
+            values
+                .into_iter()
+                .fold((V::zero(), self.acc, 0u64), |(sum, acc, events), value| {
+                    #[cfg(feature = "alloc")]
+                    if let Some(histogram) = histogram {
+                        histogram.record(value.to_f64());
+                    }
+
+                    (
+                        sum.accumulate(value),
+                        A::fold(acc, value),
+                        events.saturating_add(1),
+                    )
+                });
+
+        self.sum = self.sum.accumulate(sum);
+        self.acc = acc;
+        self.lifetime_sum = self.lifetime_sum.accumulate(sum);
+        self.events = self.events.saturating_add(events);
+    }
+
+    /// Runs `work` and reports the count it returns alongside its result.
+    pub fn measure<R, F: FnOnce() -> (R, V)>(&mut self, work: F) -> R {
+        let (result, value) = work();
+        self.report(value);
+
+        result
+    }
+
+    /// Adds `value` to the current window, or leaves the throughput
+    /// untouched and returns an error if that would overflow `V`. A failed
+    /// report neither counts as activity nor closes an
+    /// [`auto_reset`](ThroughputBuilder::auto_reset) window.
+    pub fn checked_report(&mut self, value: V) -> Result<(), OverflowError> {
+        self.sum.checked_accumulate(value).ok_or(OverflowError)?;
+        self.before_report();
+        // the window may have been closed, so the sum is added anew
+        self.sum = self.sum.accumulate(value);
+        self.acc = A::fold(self.acc, value);
+        self.lifetime_sum = self.lifetime_sum.accumulate(value);
+        self.events = self.events.saturating_add(1);
+        self.record(value);
+        Ok(())
+    }
+
+    /// Reports `value` and returns the instantaneous rate since the previous
+    /// call, i.e. `value` divided by the time between both calls. Returns
+    /// `None` on the first call or if no time passed in between.
+    pub fn report_instant(&mut self, value: V) -> Option<f64> {
+        self.report(value);
+
+        let gap = self
+            .last_report_time
+            .replace(self.initial_time.renew())?
+            .elapsed();
+        let denominator = gap.as_secs_f64();
+        if denominator == 0.0 {
+            None
+        } else {
+            Some(value.to_f64() / denominator)
+        }
     }
 
+    /// Starts a new window, i.e. both [`rebase_window`](Self::rebase_window)
+    /// and [`clear_count`](Self::clear_count).
     pub fn reset(&mut self) {
-        self.initial_time = T::now();
-        self.sum = 0;
+        self.rebase_window();
+        self.clear_count();
     }
 
-    pub fn throughput(&mut self) -> Option<f64> {
-        let elapsed: Duration = self.initial_time.elapsed();
-        let denominator =
-            f64::from(elapsed.as_secs() as u32) + f64::from(elapsed.subsec_millis()) / 1000.0;
-        let tp = if denominator == 0.0 {
+    /// Restarts the elapsed time of the window but keeps the values
+    /// reported so far, e.g. to measure the rate of a remaining count over
+    /// a fresh window.
+    pub fn rebase_window(&mut self) {
+        self.initial_time = self.initial_time.renew();
+        self.paused_since = self.paused_since.as_ref().map(T::renew);
+        self.paused_duration = Duration::default();
+    }
+
+    /// Discards the values reported so far but keeps the elapsed time of
+    /// the window.
+    pub fn clear_count(&mut self) {
+        self.sum = V::zero();
+        self.acc = A::zero();
+        self.events = 0;
+        self.latency_sum = Duration::default();
+        self.latency_events = 0;
+        #[cfg(feature = "alloc")]
+        if let Some(histogram) = &mut self.histogram {
+            histogram.clear();
+        }
+    }
+
+    /// Stops counting elapsed time, e.g. while a pipeline is idle on
+    /// purpose, so the pause doesn't dilute the rate. Reports are still
+    /// accepted. Does nothing if already paused.
+    pub fn pause(&mut self) {
+        if self.paused_since.is_none() {
+            self.paused_since = Some(self.initial_time.renew());
+        }
+    }
+
+    /// Continues counting elapsed time after [`pause`](Self::pause). Does
+    /// nothing if not paused.
+    pub fn resume(&mut self) {
+        if let Some(paused_since) = self.paused_since.take() {
+            self.paused_duration = self.paused_duration.saturating_add(paused_since.elapsed());
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_since.is_some()
+    }
+
+    /// Returns the sum reported in the current window.
+    pub fn count(&self) -> V {
+        self.sum
+    }
+
+    /// Returns the sum reported over all windows. Unlike the window sum it
+    /// is not cleared by resets, only by [`reset_total`](Self::reset_total).
+    pub fn total(&self) -> V {
+        self.lifetime_sum
+    }
+
+    pub fn reset_total(&mut self) {
+        self.lifetime_sum = V::zero();
+    }
+
+    /// Returns the number of reports in the current window. The count
+    /// saturates at `u64::MAX`, which even a billion reports per second
+    /// take centuries to reach.
+    pub fn event_count(&self) -> u64 {
+        self.events
+    }
+
+    /// Returns whether anything was reported in the current window, e.g. to
+    /// skip logging idle windows.
+    pub fn has_data(&self) -> bool {
+        self.events > 0 || self.sum.to_f64() != 0.0
+    }
+
+    /// Returns whether nothing was reported for longer than `max_idle`,
+    /// counting from the start of the window if nothing was reported yet.
+    /// In contrast to a low rate, this tells a stream that stopped from one
+    /// that is merely slow.
+    pub fn is_stalled(&self, max_idle: Duration) -> bool {
+        let since = self.last_activity.as_ref().unwrap_or(&self.initial_time);
+        since.elapsed() > max_idle
+    }
+
+    /// Returns the average reported value in the current window, or `None`
+    /// if nothing was reported.
+    pub fn average_value(&self) -> Option<f64> {
+        if self.events == 0 {
             None
         } else {
-            Some(f64::from(self.sum) / denominator)
-        };
+            Some(self.sum.to_f64() / self.events as f64)
+        }
+    }
+
+    /// Returns the average latency passed to
+    /// [`report_with_latency`](Self::report_with_latency) in the current
+    /// window, or `None` if none was passed. Plain reports don't count.
+    pub fn average_latency(&self) -> Option<Duration> {
+        if self.latency_events == 0 {
+            None
+        } else {
+            Some(self.latency_sum.div_f64(self.latency_events as f64))
+        }
+    }
+
+    /// Returns how long the current window has been running, excluding
+    /// pauses.
+    pub fn elapsed(&self) -> Duration {
+        let paused = self.paused_duration
+            + self
+                .paused_since
+                .as_ref()
+                .map_or(Duration::default(), T::elapsed);
+
+        self.initial_time.elapsed().saturating_sub(paused)
+    }
+
+    /// Captures the current window so it can be persisted and later resumed
+    /// with [`from_state`](Self::from_state).
+    pub fn to_state(&self) -> ThroughputState<V> {
+        ThroughputState {
+            sum: self.sum,
+            events: self.events,
+            elapsed: self.elapsed(),
+        }
+    }
+
+    /// Moves the accumulated sums and the configuration over to the time
+    /// source `U`, e.g. from a test clock to [`Instant`]. Time values cannot
+    /// be translated between sources, so the window restarts on `U`: its
+    /// elapsed time starts from zero while the sum is kept.
+    pub fn into_time_source<U: TimeSource>(self) -> Throughput<U, V, A> {
+        Throughput {
+            initial_time: U::now(),
+            paused_since: self.paused_since.map(|_| U::now()),
+            paused_duration: Duration::default(),
+            sum: self.sum,
+            acc: self.acc,
+            events: self.events,
+            latency_sum: self.latency_sum,
+            latency_events: self.latency_events,
+            lifetime_sum: self.lifetime_sum,
+            last_report_time: None,
+            last_activity: None,
+            peak: self.peak,
+            min: self.min,
+            completed_window: self.completed_window,
+            #[cfg(feature = "alloc")]
+            histogram: self.histogram,
+            options: self.options,
+            _aggregator: PhantomData,
+        }
+    }
+
+    // a rate which is not finite stems from a bogus elapsed time, e.g. zero
+    // for a sum, or an empty window, e.g. for a maximum, and is dropped
+    fn rate_over(&self, elapsed: Duration) -> Option<f64> {
+        Some(A::finish(self.acc, elapsed) * self.options.scale).filter(|rate| rate.is_finite())
+    }
+
+    // the gate of every rate: a window which has not run for `min_elapsed`
+    // has no rate and is kept open
+    fn below_min_elapsed(&self, elapsed: Duration) -> bool {
+        elapsed < self.options.min_elapsed
+    }
+
+    /// Returns the rate of the current window without resetting it.
+    #[must_use]
+    pub fn peek_throughput(&self) -> Option<f64> {
+        let elapsed = self.elapsed();
+        if self.below_min_elapsed(elapsed) {
+            return None;
+        }
+
+        self.rate_over(elapsed)
+            .map(|rate| rate * self.options.unit.seconds())
+    }
+
+    /// Returns the unit of the rates returned by
+    /// [`throughput`](Self::throughput), per second unless configured
+    /// otherwise with [`ThroughputBuilder::unit`].
+    pub fn unit(&self) -> RateUnit {
+        self.options.unit
+    }
+
+    /// Returns the resolution of the clock `T`, i.e. the smallest step it
+    /// advances by.
+    ///
+    /// The clock is sampled a few times by reading it until it advances
+    /// and keeping the smallest step, so the result is exact for a clock
+    /// of coarse granularity and an upper bound for a fine one, where the
+    /// reads themselves take most of the time. It is zero if the clock
+    /// didn't advance within a million reads per sample, e.g. for a
+    /// clock which is advanced by hand. Each call samples anew, busy
+    /// waiting for at least a few ticks.
+    pub fn clock_resolution(&self) -> Duration {
+        sample_resolution::<T>()
+    }
+
+    /// Multiplies all rates by `factor`, e.g. a compression ratio to get
+    /// the rate of uncompressed bytes from compressed sizes. The scale is
+    /// applied to the rate rather than to every report, so no precision is
+    /// lost to rounding values; [`count`](Self::count) and
+    /// [`total`](Self::total) stay unscaled.
+    pub fn set_scale(&mut self, factor: f64) {
+        self.options.scale = factor;
+    }
+
+    fn update_extremes(&mut self, rate: f64) {
+        self.peak = Some(self.peak.map_or(rate, |peak| peak.max(rate)));
+        self.min = Some(self.min.map_or(rate, |min| min.min(rate)));
+    }
+
+    /// Returns the rate of the current window and starts a new one.
+    ///
+    /// If a [threshold](Self::set_threshold) is set and the rate crosses
+    /// it, the callback is invoked with the rate before returning.
+    ///
+    /// Ignoring the rate is warned about, as the window is closed anyway:
+    ///
+    /// ```compile_fail
+    /// #![deny(unused_must_use)]
+    /// let mut tp: tp::Throughput<std::time::Instant> = tp::Throughput::new();
+    /// tp.throughput();
+    /// ```
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput(&mut self) -> Option<f64> {
+        if self.below_min_elapsed(self.elapsed()) {
+            return None;
+        }
+
+        let tp = self.peek_throughput();
+        self.close_window(tp)
+    }
+
+    /// Like [`throughput`](Self::throughput), but measures the window up to
+    /// `now` instead of reading the clock, e.g. to close several windows at
+    /// the same instant. The new window starts at `now`.
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput_at(&mut self, now: T) -> Option<f64> {
+        let paused = self.paused_duration
+            + self
+                .paused_since
+                .as_ref()
+                .map_or(Duration::default(), |since| now.duration_since(since));
+        let elapsed = now
+            .duration_since(&self.initial_time)
+            .saturating_sub(paused);
+        if self.below_min_elapsed(elapsed) {
+            return None;
+        }
+
+        let tp = self
+            .rate_over(elapsed)
+            .map(|rate| rate * self.options.unit.seconds());
+        let tp = self.close_window(tp);
+        self.initial_time = now;
+
+        tp
+    }
+
+    // fires the threshold, records the extremes and starts a new window
+    fn close_window(&mut self, tp: Option<f64>) -> Option<f64> {
+        if self.options.skip_window {
+            self.options.skip_window = false;
+            self.reset();
+            return None;
+        }
+
+        #[cfg(feature = "alloc")]
+        if let (Some(rate), Some((bound, cb))) = (tp, &self.options.threshold) {
+            if bound.is_crossed_by(rate) {
+                cb(rate);
+            }
+        }
+        if let (Some(rate), true) = (tp, self.options.track_peak) {
+            self.update_extremes(rate);
+        }
 
         self.reset();
 
         tp
     }
-}
 
-impl<T: TimeSource> Default for Throughput<T> {
-    fn default() -> Self {
-        Self::new()
+    /// Closes the current window and returns its rate. The same as
+    /// [`throughput`](Self::throughput), for call sites rolling over
+    /// intervals; use [`reset`](Self::reset) to discard the window instead.
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn reset_and_report(&mut self) -> Option<f64> {
+        self.throughput()
     }
-}
 
-#[derive(Debug)]
-pub struct ThroughputSynchronized<T: TimeSource> {
-    tp_unsynchronized: Mutex<Throughput<T>>,
-}
+    /// Like [`throughput`](Self::throughput), but rounds the rate to
+    /// `decimals` decimal places, e.g. for log output.
+    #[cfg(feature = "std")]
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput_rounded(&mut self, decimals: u32) -> Option<f64> {
+        let scale = 10f64.powi(decimals as i32);
+        self.throughput().map(|rate| (rate * scale).round() / scale)
+    }
 
-impl<T: TimeSource> ThroughputSynchronized<T> {
-    pub fn new() -> Self {
-        Self {
-            tp_unsynchronized: Mutex::new(Throughput::new()),
+    /// Like [`throughput`](Self::throughput), but tells apart why there is
+    /// no rate. Unlike `throughput`, an empty window is an error rather
+    /// than a rate of zero.
+    pub fn try_throughput(&mut self) -> Result<f64, ThroughputError> {
+        if self.below_min_elapsed(self.elapsed()) {
+            return Err(ThroughputError::BelowMinElapsed);
+        }
+
+        let no_data = !self.has_data();
+        let tp = self.throughput();
+
+        if no_data {
+            Err(ThroughputError::NoData)
+        } else {
+            tp.ok_or(ThroughputError::ZeroElapsed)
+        }
+    }
+
+    /// Returns the name set with [`set_name`](Self::set_name) or
+    /// [`ThroughputBuilder::name`].
+    #[cfg(feature = "alloc")]
+    pub fn name(&self) -> Option<&str> {
+        self.options.name.as_deref()
+    }
+
+    /// Names the throughput, e.g. after what it measures. The name prefixes
+    /// the `Display` output and is recorded by `log_throughput` with the
+    /// `tracing` feature.
+    #[cfg(feature = "alloc")]
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.options.name = Some(name.into());
+    }
+
+    /// Sets an alarm which invokes `cb` with the rate whenever
+    /// [`throughput`](Self::throughput) computes a rate beyond `bound`.
+    /// Replaces a previously set threshold. Windows without a rate never
+    /// fire.
+    #[cfg(feature = "alloc")]
+    pub fn set_threshold(&mut self, bound: Threshold, cb: Box<dyn Fn(f64) + Send + Sync>) {
+        self.options.threshold = Some((bound, Arc::from(cb)));
+    }
+
+    /// Like [`throughput`](Self::throughput), but expresses the rate in
+    /// `unit` instead of the configured one.
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput_per(&mut self, unit: RateUnit) -> Option<f64> {
+        let configured = self.options.unit;
+        self.throughput()
+            .map(|rate| rate / configured.seconds() * unit.seconds())
+    }
+
+    /// Like [`throughput`](Self::throughput), but for values counting bytes
+    /// returns the rate in bits, see
+    /// [`format_bits_per_sec`](crate::format::format_bits_per_sec).
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput_bits(&mut self) -> Option<f64> {
+        self.throughput().map(|rate| rate * 8.0)
+    }
+
+    /// Like [`throughput`](Self::throughput), but casts the rate to `f32`
+    /// for consumers which only take that. The rate is still computed in
+    /// `f64`; the cast keeps about 7 significant digits, so e.g. a rate of
+    /// `123456789.0` becomes `123456792.0`. Rates beyond the range of `f32`
+    /// are `None`.
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput_f32(&mut self) -> Option<f32> {
+        self.throughput()
+            .map(|rate| rate as f32)
+            .filter(|rate| rate.is_finite())
+    }
+
+    /// Returns the sum of the current window divided by the nominal
+    /// `window` rather than the measured elapsed time, and starts a new
+    /// window. Useful when reporting exactly once per fixed interval, where
+    /// the measured time jitters. Returns `None` if `window` is zero.
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput_over(&mut self, window: Duration) -> Option<f64> {
+        if self.below_min_elapsed(self.elapsed()) {
+            return None;
+        }
+
+        let tp = self
+            .rate_over(window)
+            .map(|rate| rate * self.options.unit.seconds());
+        self.close_window(tp)
+    }
+
+    /// Returns rate, sum, event count and elapsed time of the current
+    /// window without resetting it. All of them refer to the same instant.
+    #[must_use]
+    pub fn peek_snapshot(&self) -> Snapshot<V> {
+        let elapsed = self.elapsed();
+        let rate = if self.below_min_elapsed(elapsed) {
+            None
+        } else {
+            self.rate_over(elapsed)
+        };
+
+        Snapshot {
+            rate,
+            sum: self.sum,
+            events: self.events,
+            elapsed,
         }
     }
 
-    pub fn report(&self, value: u32) {
-        self.tp_unsynchronized.lock().unwrap().report(value);
-    }
+    /// Like [`peek_snapshot`](Self::peek_snapshot), but closes the window
+    /// as [`throughput`](Self::throughput) does.
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn snapshot(&mut self) -> Snapshot<V> {
+        let mut snapshot = self.peek_snapshot();
+        if self.below_min_elapsed(snapshot.elapsed) {
+            return snapshot;
+        }
+
+        // the threshold and the extremes refer to rates in the configured
+        // unit, while the snapshot is per second
+        let unit = self.options.unit.seconds();
+        snapshot.rate = self
+            .close_window(snapshot.rate.map(|rate| rate * unit))
+            .map(|rate| rate / unit);
+
+        snapshot
+    }
+
+    /// Returns rate, elapsed time and sum of the current window and starts a
+    /// new one. The rate is computed from the returned elapsed time, so
+    /// unlike separate calls to [`elapsed`](Self::elapsed) and
+    /// [`throughput`](Self::throughput) the values agree with each other;
+    /// the new window starts at a later reading of the clock. This is a
+    /// tuple form of [`snapshot`](Self::snapshot).
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn sample(&mut self) -> (Option<f64>, Duration, V) {
+        let snapshot = self.snapshot();
+
+        (snapshot.rate, snapshot.elapsed, snapshot.sum)
+    }
+
+    /// Like [`throughput`](Self::throughput), but additionally records the
+    /// rate as the new peak or minimum if it exceeds all previous ones.
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput_and_update_peak(&mut self) -> Option<f64> {
+        let tp = self.throughput();
+        if let Some(rate) = tp {
+            self.update_extremes(rate);
+        }
+
+        tp
+    }
+
+    /// Returns the highest rate returned by
+    /// [`throughput_and_update_peak`](Self::throughput_and_update_peak), or
+    /// by [`throughput`](Self::throughput) if peak tracking is enabled with
+    /// [`ThroughputBuilder::track_peak`]. Resets do not clear the peak.
+    pub fn peak(&self) -> Option<f64> {
+        self.peak
+    }
+
+    /// Returns the lowest rate, recorded along with the [`peak`](Self::peak),
+    /// e.g. to detect stalls. Windows without a rate are not considered.
+    pub fn min(&self) -> Option<f64> {
+        self.min
+    }
+
+    /// Clears both [`peak`](Self::peak) and [`min`](Self::min).
+    pub fn reset_extremes(&mut self) {
+        self.peak = None;
+        self.min = None;
+    }
+}
+
+// the state of other aggregators cannot be restored from the sum
+impl<T: TimeSource, V: Accumulate> Throughput<T, V> {
+    /// Resumes a window captured by [`to_state`](Self::to_state). Since time
+    /// source values cannot be persisted, the window starts at
+    /// [`T::now_minus`](TimeSource::now_minus) the stored elapsed time.
+    pub fn from_state(state: ThroughputState<V>) -> Self {
+        Self {
+            sum: state.sum,
+            acc: state.sum,
+            lifetime_sum: state.sum,
+            events: state.events,
+            initial_time: T::now_minus(state.elapsed),
+            ..Self::new()
+        }
+    }
+
+    /// Creates a window which already holds `sum` and has been running for
+    /// `elapsed`, e.g. to resume from persisted counters. The restored sum
+    /// is not counted as report events.
+    pub fn with_state(sum: V, elapsed: Duration) -> Self {
+        Self::from_state(ThroughputState {
+            sum,
+            events: 0,
+            elapsed,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<V: Accumulate, A: Aggregator<V>> Throughput<crate::testing::FnTimeSource, V, A> {
+    /// Creates a throughput whose windows report `elapsed()` as elapsed
+    /// time, e.g. `Throughput::with_fn(|| Duration::from_secs(2))`.
+    pub fn with_fn<F: Fn() -> Duration + Send + Sync + 'static>(elapsed: F) -> Self {
+        Self {
+            initial_time: crate::testing::FnTimeSource::new(elapsed),
+            ..Self::new()
+        }
+    }
+}
+
+impl<T: TimeSource, A: Aggregator<i64>> Throughput<T, i64, A> {
+    /// Adjusts the sum by `delta`, which may be negative, e.g. for the net
+    /// change of a queue depth. The rate becomes negative if decrements
+    /// outweigh increments.
+    pub fn report_delta(&mut self, delta: i64) {
+        self.report(delta);
+    }
+}
+
+impl<T: TimeSource, V: Accumulate + fmt::Debug, A: Aggregator<V>> fmt::Debug
+    for Throughput<T, V, A>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Throughput");
+        d.field("initial_time", &format_args!("_"))
+            .field("paused", &self.is_paused())
+            .field("paused_duration", &self.paused_duration)
+            .field("sum", &self.sum)
+            .field("events", &self.events)
+            .field("latency_sum", &self.latency_sum)
+            .field("latency_events", &self.latency_events)
+            .field("lifetime_sum", &self.lifetime_sum)
+            .field("peak", &self.peak)
+            .field("min", &self.min)
+            .field("unit", &self.options.unit)
+            .field("track_peak", &self.options.track_peak)
+            .field("skip_window", &self.options.skip_window)
+            .field("auto_reset", &self.options.auto_reset)
+            .field("completed_window", &self.completed_window)
+            .field("min_elapsed", &self.options.min_elapsed)
+            .field("scale", &self.options.scale);
+        #[cfg(feature = "alloc")]
+        d.field("histogram", &self.histogram)
+            .field("name", &self.options.name)
+            .field(
+                "threshold",
+                &self.options.threshold.as_ref().map(|(bound, _)| bound),
+            );
+        d.finish()
+    }
+}
+
+/// Formats the current window like its [`Snapshot`] without resetting
+/// it, prefixed by the [name](Throughput::name) if any, e.g.
+/// `ingest: 12.5 units/s over 2.00s`.
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> fmt::Display for Throughput<T, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "alloc")]
+        if let Some(name) = &self.options.name {
+            write!(f, "{}: ", name)?;
+        }
+        write!(f, "{}", self.peek_snapshot())
+    }
+}
+
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> Default for Throughput<T, V, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `tp += value` is shorthand for [`tp.report(value)`](Throughput::report).
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> AddAssign<V> for Throughput<T, V, A> {
+    fn add_assign(&mut self, value: V) {
+        self.report(value);
+    }
+}
+
+/// Reports every value, as [`Throughput::report_iter`] does.
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> Extend<V> for Throughput<T, V, A> {
+    fn extend<I: IntoIterator<Item = V>>(&mut self, values: I) {
+        self.report_iter(values);
+    }
+}
+
+impl<'a, T: TimeSource, V: Accumulate, A: Aggregator<V>> Extend<&'a V> for Throughput<T, V, A> {
+    fn extend<I: IntoIterator<Item = &'a V>>(&mut self, values: I) {
+        self.report_iter(values.into_iter().copied());
+    }
+}
+
+/// Starts a window with all values reported, i.e. the rate covers the time
+/// from collecting until the window is closed.
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> FromIterator<V> for Throughput<T, V, A> {
+    fn from_iter<I: IntoIterator<Item = V>>(values: I) -> Self {
+        let mut tp = Self::new();
+        tp.extend(values);
+        tp
+    }
+}
+
+#[cfg(feature = "std")]
+pub struct ThroughputSynchronized<
+    T: TimeSource,
+    V: Accumulate = u64,
+    A: Aggregator<V> = SumPerSecond,
+> {
+    tp_unsynchronized: Mutex<Throughput<T, V, A>>,
+}
+
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate + fmt::Debug, A: Aggregator<V>> fmt::Debug
+    for ThroughputSynchronized<T, V, A>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("ThroughputSynchronized");
+        match self.tp_unsynchronized.try_lock() {
+            Ok(tp) => d.field("tp_unsynchronized", &*tp),
+            Err(TryLockError::Poisoned(err)) => d.field("tp_unsynchronized", err.get_ref()),
+            Err(TryLockError::WouldBlock) => {
+                d.field("tp_unsynchronized", &format_args!("<locked>"))
+            }
+        };
+        d.field("poisoned", &self.tp_unsynchronized.is_poisoned())
+            .finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> ThroughputSynchronized<T, V, A> {
+    pub fn new() -> Self {
+        Self {
+            tp_unsynchronized: Mutex::new(Throughput::new()),
+        }
+    }
+
+    /// Returns a throughput which is only created on first use, so it can
+    /// be placed in a `static`, see [`LazyThroughputSynchronized`].
+    pub const fn new_lazy() -> LazyThroughputSynchronized<T, V, A> {
+        LazyThroughputSynchronized {
+            tp: OnceLock::new(),
+        }
+    }
+
+    // a panic elsewhere while holding the lock cannot leave the throughput
+    // in an inconsistent state, so measuring continues after poisoning
+    fn lock(&self) -> MutexGuard<'_, Throughput<T, V, A>> {
+        self.tp_unsynchronized
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Consumes the wrapper and returns the throughput, even if the lock
+    /// is poisoned.
+    pub fn into_inner(self) -> Throughput<T, V, A> {
+        self.tp_unsynchronized
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the throughput without locking, as the exclusive borrow
+    /// rules out other users. Recovers from poisoning like all other
+    /// methods.
+    pub fn get_mut(&mut self) -> &mut Throughput<T, V, A> {
+        self.tp_unsynchronized
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn report(&self, value: V) {
+        self.lock().report(value);
+    }
+
+    pub fn report_with_latency(&self, value: V, latency: Duration) {
+        self.lock().report_with_latency(value, latency);
+    }
+
+    /// Reports all `values` while taking the lock only once.
+    pub fn report_many(&self, values: &[V]) {
+        self.lock().report_many(values);
+    }
+
+    pub fn report_iter<I: IntoIterator<Item = V>>(&self, values: I) {
+        self.lock().report_iter(values);
+    }
+
+    pub fn checked_report(&self, value: V) -> Result<(), OverflowError> {
+        self.lock().checked_report(value)
+    }
+
+    pub fn report_instant(&self, value: V) -> Option<f64> {
+        self.lock().report_instant(value)
+    }
+
+    /// Reports `value` unless another thread holds the lock, in which case
+    /// the value is dropped rather than buffered and `false` is returned.
+    /// Never blocks.
+    pub fn try_report(&self, value: V) -> bool {
+        match self.tp_unsynchronized.try_lock() {
+            Ok(mut tp) => tp.report(value),
+            Err(TryLockError::Poisoned(err)) => err.into_inner().report(value),
+            Err(TryLockError::WouldBlock) => return false,
+        }
+
+        true
+    }
+
+    /// Runs `work` without holding the lock and reports the count it returns
+    /// alongside its result.
+    pub fn measure<R, F: FnOnce() -> (R, V)>(&self, work: F) -> R {
+        let (result, value) = work();
+        self.report(value);
+
+        result
+    }
+
+    /// Returns a guard which reports `value` when it goes out of scope, also
+    /// on early returns and unwinding panics.
+    pub fn scope(&self, value: V) -> ReportGuard<'_, T, V, A> {
+        ReportGuard { tp: self, value }
+    }
+
+    pub fn reset(&self) {
+        self.lock().reset();
+    }
+
+    pub fn rebase_window(&self) {
+        self.lock().rebase_window();
+    }
+
+    pub fn clear_count(&self) {
+        self.lock().clear_count();
+    }
+
+    pub fn pause(&self) {
+        self.lock().pause();
+    }
+
+    pub fn resume(&self) {
+        self.lock().resume();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.lock().is_paused()
+    }
+
+    pub fn count(&self) -> V {
+        self.lock().count()
+    }
+
+    pub fn total(&self) -> V {
+        self.lock().total()
+    }
+
+    pub fn reset_total(&self) {
+        self.lock().reset_total();
+    }
+
+    pub fn event_count(&self) -> u64 {
+        self.lock().event_count()
+    }
+
+    pub fn has_data(&self) -> bool {
+        self.lock().has_data()
+    }
+
+    pub fn is_stalled(&self, max_idle: Duration) -> bool {
+        self.lock().is_stalled(max_idle)
+    }
+
+    pub fn average_value(&self) -> Option<f64> {
+        self.lock().average_value()
+    }
+
+    pub fn average_latency(&self) -> Option<Duration> {
+        self.lock().average_latency()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.lock().elapsed()
+    }
+
+    #[must_use]
+    pub fn peek_throughput(&self) -> Option<f64> {
+        self.lock().peek_throughput()
+    }
+
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput(&self) -> Option<f64> {
+        self.lock().throughput()
+    }
+
+    pub fn try_throughput(&self) -> Result<f64, ThroughputError> {
+        self.lock().try_throughput()
+    }
+
+    /// Returns a consistent view of the current window taken under a single
+    /// lock. Unlike [`Throughput::snapshot`] this does not reset the window,
+    /// so it can be used by a scraper alongside the regular consumer.
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot<V> {
+        self.lock().peek_snapshot()
+    }
+
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn reset_and_report(&self) -> Option<f64> {
+        self.lock().reset_and_report()
+    }
+
+    /// Returns a copy of the [name](Throughput::name), as it cannot be
+    /// borrowed past the lock.
+    pub fn name(&self) -> Option<String> {
+        self.lock().name().map(String::from)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> Default for ThroughputSynchronized<T, V, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Wraps a configured throughput, e.g. one created with
+/// [`Throughput::builder`].
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> From<Throughput<T, V, A>>
+    for ThroughputSynchronized<T, V, A>
+{
+    fn from(tp: Throughput<T, V, A>) -> Self {
+        Self {
+            tp_unsynchronized: Mutex::new(tp),
+        }
+    }
+}
+
+/// Reports a value to a [`ThroughputSynchronized`] when dropped, see
+/// [`ThroughputSynchronized::scope`].
+#[cfg(feature = "std")]
+#[must_use = "the value is reported when the guard is dropped"]
+pub struct ReportGuard<'a, T: TimeSource, V: Accumulate = u64, A: Aggregator<V> = SumPerSecond> {
+    tp: &'a ThroughputSynchronized<T, V, A>,
+    value: V,
+}
+
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> Drop for ReportGuard<'_, T, V, A> {
+    fn drop(&mut self) {
+        self.tp.report(self.value);
+    }
+}
+
+/// [`ThroughputSynchronized`] created on first use, see
+/// [`ThroughputSynchronized::new_lazy`].
+///
+/// It dereferences to the synchronized throughput, whose first window
+/// starts with the first access, e.g. the first report, rather than at
+/// construction. Construction is `const`, so it can be placed in a
+/// `static`.
+#[cfg(feature = "std")]
+pub struct LazyThroughputSynchronized<
+    T: TimeSource,
+    V: Accumulate = u64,
+    A: Aggregator<V> = SumPerSecond,
+> {
+    tp: OnceLock<ThroughputSynchronized<T, V, A>>,
+}
+
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> Deref for LazyThroughputSynchronized<T, V, A> {
+    type Target = ThroughputSynchronized<T, V, A>;
+
+    fn deref(&self) -> &Self::Target {
+        self.tp.get_or_init(ThroughputSynchronized::new)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate + fmt::Debug, A: Aggregator<V>> fmt::Debug
+    for LazyThroughputSynchronized<T, V, A>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyThroughputSynchronized")
+            .field("tp", &self.tp)
+            .finish()
+    }
+}
+
+/// [`ThroughputSynchronized`] passing its final rate to a callback when
+/// dropped, e.g. to log it.
+///
+/// It dereferences to the synchronized throughput. On drop the current
+/// window is closed as by [`throughput`](ThroughputSynchronized::throughput)
+/// and its rate is passed to the callback. Like all other accesses, this
+/// recovers from a poisoned lock, so dropping never panics by itself.
+#[cfg(feature = "std")]
+#[must_use = "the rate is passed to the callback when the wrapper is dropped"]
+pub struct LogOnDrop<T: TimeSource, V: Accumulate = u64, A: Aggregator<V> = SumPerSecond> {
+    tp: ThroughputSynchronized<T, V, A>,
+    on_drop: Option<Box<dyn FnOnce(Option<f64>) + Send>>,
+}
+
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> LogOnDrop<T, V, A> {
+    pub fn new<F>(tp: ThroughputSynchronized<T, V, A>, on_drop: F) -> Self
+    where
+        F: FnOnce(Option<f64>) + Send + 'static,
+    {
+        Self {
+            tp,
+            on_drop: Some(Box::new(on_drop)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> Deref for LogOnDrop<T, V, A> {
+    type Target = ThroughputSynchronized<T, V, A>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tp
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> Drop for LogOnDrop<T, V, A> {
+    fn drop(&mut self) {
+        if let Some(on_drop) = self.on_drop.take() {
+            on_drop(self.tp.get_mut().throughput());
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate + fmt::Debug, A: Aggregator<V>> fmt::Debug
+    for LogOnDrop<T, V, A>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogOnDrop").field("tp", &self.tp).finish()
+    }
+}
+
+/// Builder for a [`Throughput`] with non-default options, see
+/// [`Throughput::builder`].
+pub struct ThroughputBuilder<T: TimeSource, V: Accumulate = u64, A: Aggregator<V> = SumPerSecond> {
+    options: Options,
+    reject_below_resolution: bool,
+    _marker: ThroughputMarker<T, V, A>,
+}
+
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> ThroughputBuilder<T, V, A> {
+    pub fn new() -> Self {
+        Self {
+            options: Options::default(),
+            reject_below_resolution: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets the unit of the rates returned by
+    /// [`Throughput::throughput`] and [`Throughput::peek_throughput`].
+    pub fn unit(mut self, unit: RateUnit) -> Self {
+        self.options.unit = unit;
+        self
+    }
+
+    /// Makes [`Throughput::throughput`] record the peak and minimum rate, as
+    /// [`Throughput::throughput_and_update_peak`] does.
+    pub fn track_peak(mut self, track_peak: bool) -> Self {
+        self.options.track_peak = track_peak;
+        self
+    }
+
+    /// Makes the first [`Throughput::throughput`] return `None` while still
+    /// starting a new window, e.g. to exclude warmup from benchmarks.
+    pub fn skip_first(mut self, skip_first: bool) -> Self {
+        self.options.skip_window = skip_first;
+        self
+    }
+
+    /// Makes the first report after `period` close the current window, as
+    /// [`Throughput::throughput`] does, before adding its value. The rate
+    /// of the closed window is kept for
+    /// [`Throughput::completed_window`]. Windows only close on reports, so
+    /// they last at least `period`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn auto_reset(mut self, period: Duration) -> Self {
+        assert!(!period.is_zero(), "period must be non-zero");
+
+        self.options.auto_reset = Some(period);
+        self
+    }
+
+    /// Makes [`Throughput::throughput`] and [`Throughput::peek_throughput`]
+    /// return `None` until the window has run for at least `min_elapsed`,
+    /// without closing it. This suppresses the huge, meaningless rates of
+    /// reads right after the window started.
+    pub fn min_elapsed(mut self, min_elapsed: Duration) -> Self {
+        self.options.min_elapsed = min_elapsed;
+        self
+    }
+
+    /// Like [`min_elapsed`](Self::min_elapsed), but makes the window run
+    /// for more than the [clock resolution](Throughput::clock_resolution),
+    /// which is measured once by [`build`](Self::build). On clocks with a
+    /// coarse granularity, e.g. 15ms, shorter windows measure an elapsed
+    /// time of zero or one tick regardless of the time actually passed.
+    pub fn reject_below_resolution(mut self, reject: bool) -> Self {
+        self.reject_below_resolution = reject;
+        self
+    }
+
+    /// See [`Throughput::set_name`].
+    #[cfg(feature = "alloc")]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.options.name = Some(name.into());
+        self
+    }
+
+    /// See [`Throughput::set_threshold`].
+    #[cfg(feature = "alloc")]
+    pub fn threshold(mut self, bound: Threshold, cb: Box<dyn Fn(f64) + Send + Sync>) -> Self {
+        self.options.threshold = Some((bound, Arc::from(cb)));
+        self
+    }
+
+    /// Creates the throughput, starting its first window.
+    pub fn build(mut self) -> Throughput<T, V, A> {
+        if self.reject_below_resolution {
+            // a window has to span more than one tick
+            let resolution = sample_resolution::<T>() + Duration::from_nanos(1);
+            self.options.min_elapsed = self.options.min_elapsed.max(resolution);
+        }
+
+        Throughput {
+            options: self.options,
+            ..Throughput::new()
+        }
+    }
+}
+
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> fmt::Debug for ThroughputBuilder<T, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("ThroughputBuilder");
+        d.field("unit", &self.options.unit)
+            .field("track_peak", &self.options.track_peak)
+            .field("skip_first", &self.options.skip_window)
+            .field("reject_below_resolution", &self.reject_below_resolution)
+            .field("auto_reset", &self.options.auto_reset)
+            .field("min_elapsed", &self.options.min_elapsed);
+        #[cfg(feature = "alloc")]
+        d.field("name", &self.options.name).field(
+            "threshold",
+            &self.options.threshold.as_ref().map(|(bound, _)| bound),
+        );
+        d.finish()
+    }
+}
+
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> Default for ThroughputBuilder<T, V, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the combined rate of several throughputs, e.g. of one
+/// per worker shard.
+///
+/// The sums of all windows are added up and divided by the longest elapsed
+/// window, i.e. the combined window reaches back to the earliest start.
+/// With differing start times this understates rather than overstates the
+/// rate. Returns `None` if `tps` is empty or no time elapsed.
+pub fn combined_throughput<T: TimeSource, V: Accumulate>(tps: &[&Throughput<T, V>]) -> Option<f64> {
+    let (sum, elapsed) = tps
+        .iter()
+        .fold((V::zero(), Duration::default()), |(sum, elapsed), tp| {
+            (sum.accumulate(tp.count()), elapsed.max(tp.elapsed()))
+        });
+
+    let denominator = elapsed.as_secs_f64();
+    if denominator == 0.0 {
+        None
+    } else {
+        Some(sum.to_f64() / denominator)
+    }
+}
+
+/// Like [`ThroughputSynchronized`], but backed by a [`RwLock`] so that
+/// non-mutating reads like [`peek_throughput`](Self::peek_throughput) can
+/// proceed concurrently.
+#[cfg(feature = "std")]
+pub struct ThroughputRwLock<T: TimeSource, V: Accumulate = u64, A: Aggregator<V> = SumPerSecond> {
+    tp_unsynchronized: RwLock<Throughput<T, V, A>>,
+}
+
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate + fmt::Debug, A: Aggregator<V>> fmt::Debug
+    for ThroughputRwLock<T, V, A>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("ThroughputRwLock");
+        match self.tp_unsynchronized.try_read() {
+            Ok(tp) => d.field("tp_unsynchronized", &*tp),
+            Err(TryLockError::Poisoned(err)) => d.field("tp_unsynchronized", err.get_ref()),
+            Err(TryLockError::WouldBlock) => {
+                d.field("tp_unsynchronized", &format_args!("<locked>"))
+            }
+        };
+        d.field("poisoned", &self.tp_unsynchronized.is_poisoned())
+            .finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> ThroughputRwLock<T, V, A> {
+    pub fn new() -> Self {
+        Self {
+            tp_unsynchronized: RwLock::new(Throughput::new()),
+        }
+    }
+
+    // like `ThroughputSynchronized::lock`, recovers from poisoning
+    fn read(&self) -> RwLockReadGuard<'_, Throughput<T, V, A>> {
+        self.tp_unsynchronized
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Throughput<T, V, A>> {
+        self.tp_unsynchronized
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Consumes the wrapper and returns the throughput, even if the lock
+    /// is poisoned.
+    pub fn into_inner(self) -> Throughput<T, V, A> {
+        self.tp_unsynchronized
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the throughput without locking, as the exclusive borrow
+    /// rules out other users. Recovers from poisoning like all other
+    /// methods.
+    pub fn get_mut(&mut self) -> &mut Throughput<T, V, A> {
+        self.tp_unsynchronized
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn report(&self, value: V) {
+        self.write().report(value);
+    }
+
+    pub fn report_with_latency(&self, value: V, latency: Duration) {
+        self.write().report_with_latency(value, latency);
+    }
+
+    /// Reports all `values` while taking the lock only once.
+    pub fn report_many(&self, values: &[V]) {
+        self.write().report_many(values);
+    }
+
+    pub fn report_iter<I: IntoIterator<Item = V>>(&self, values: I) {
+        self.write().report_iter(values);
+    }
+
+    pub fn checked_report(&self, value: V) -> Result<(), OverflowError> {
+        self.write().checked_report(value)
+    }
+
+    pub fn report_instant(&self, value: V) -> Option<f64> {
+        self.write().report_instant(value)
+    }
+
+    /// See [`ThroughputSynchronized::try_report`]; fails while readers hold
+    /// the lock as well.
+    pub fn try_report(&self, value: V) -> bool {
+        match self.tp_unsynchronized.try_write() {
+            Ok(mut tp) => tp.report(value),
+            Err(TryLockError::Poisoned(err)) => err.into_inner().report(value),
+            Err(TryLockError::WouldBlock) => return false,
+        }
+
+        true
+    }
+
+    pub fn reset(&self) {
+        self.write().reset();
+    }
+
+    pub fn rebase_window(&self) {
+        self.write().rebase_window();
+    }
+
+    pub fn clear_count(&self) {
+        self.write().clear_count();
+    }
+
+    pub fn pause(&self) {
+        self.write().pause();
+    }
+
+    pub fn resume(&self) {
+        self.write().resume();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.read().is_paused()
+    }
+
+    pub fn count(&self) -> V {
+        self.read().count()
+    }
+
+    pub fn total(&self) -> V {
+        self.read().total()
+    }
+
+    pub fn reset_total(&self) {
+        self.write().reset_total();
+    }
+
+    pub fn event_count(&self) -> u64 {
+        self.read().event_count()
+    }
+
+    pub fn has_data(&self) -> bool {
+        self.read().has_data()
+    }
+
+    pub fn is_stalled(&self, max_idle: Duration) -> bool {
+        self.read().is_stalled(max_idle)
+    }
+
+    pub fn average_value(&self) -> Option<f64> {
+        self.read().average_value()
+    }
+
+    pub fn average_latency(&self) -> Option<Duration> {
+        self.read().average_latency()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.read().elapsed()
+    }
+
+    #[must_use]
+    pub fn peek_throughput(&self) -> Option<f64> {
+        self.read().peek_throughput()
+    }
+
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput(&self) -> Option<f64> {
+        self.write().throughput()
+    }
+
+    pub fn try_throughput(&self) -> Result<f64, ThroughputError> {
+        self.write().try_throughput()
+    }
+
+    /// See [`ThroughputSynchronized::snapshot`].
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot<V> {
+        self.read().peek_snapshot()
+    }
+
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn reset_and_report(&self) -> Option<f64> {
+        self.write().reset_and_report()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: TimeSource, V: Accumulate, A: Aggregator<V>> Default for ThroughputRwLock<T, V, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "async")]
+pub mod tokio_async {
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use tokio::sync::{Mutex, MutexGuard};
+    use tokio::task::JoinHandle;
+
+    use crate::aggregate::SumPerSecond;
+    use crate::async_lock::AsyncLock;
+
+    impl<X> AsyncLock<X> for Mutex<X> {
+        type Guard<'a>
+            = MutexGuard<'a, X>
+        where
+            X: 'a;
+
+        fn new(value: X) -> Self {
+            Mutex::new(value)
+        }
+
+        fn lock(&self) -> impl Future<Output = Self::Guard<'_>> {
+            Mutex::lock(self)
+        }
+
+        fn into_inner(self) -> X {
+            Mutex::into_inner(self)
+        }
+    }
+
+    /// [`ThroughputAsyncSynchronized`](crate::async_lock::ThroughputAsyncSynchronized)
+    /// guarded by a `tokio` mutex.
+    pub type ThroughputAsyncSynchronized<T, V = u64, A = SumPerSecond> =
+        crate::async_lock::ThroughputAsyncSynchronized<T, Mutex<super::Throughput<T, V, A>>, V, A>;
+
+    /// Spawns a task which takes the throughput of `tp` every `period` and
+    /// passes the rate to `sink`.
+    ///
+    /// The first rate is taken one `period` after spawning. The task stops
+    /// once it holds the last reference to `tp`, or when it is aborted
+    /// through the returned handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero or if called outside of a tokio runtime.
+    pub fn spawn_interval_reporter<F>(
+        tp: Arc<ThroughputAsyncSynchronized<Instant>>,
+        period: Duration,
+        sink: F,
+    ) -> JoinHandle<()>
+    where
+        F: Fn(Option<f64>) + Send + 'static,
+    {
+        let mut interval = tokio::time::interval(period);
+
+        tokio::spawn(async move {
+            // the first tick completes immediately
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                if Arc::strong_count(&tp) == 1 {
+                    break;
+                }
+
+                let rate = tp.throughput().await;
+                sink(rate);
+            }
+        })
+    }
+}
+
+#[cfg(feature = "async-std")]
+pub mod async_std_async {
+    use async_std::sync::{Mutex, MutexGuard};
+    use std::future::Future;
+
+    use crate::aggregate::SumPerSecond;
+    use crate::async_lock::AsyncLock;
+
+    impl<X> AsyncLock<X> for Mutex<X> {
+        type Guard<'a>
+            = MutexGuard<'a, X>
+        where
+            X: 'a;
+
+        fn new(value: X) -> Self {
+            Mutex::new(value)
+        }
+
+        fn lock(&self) -> impl Future<Output = Self::Guard<'_>> {
+            Mutex::lock(self)
+        }
+
+        fn into_inner(self) -> X {
+            Mutex::into_inner(self)
+        }
+    }
+
+    /// [`ThroughputAsyncSynchronized`](crate::async_lock::ThroughputAsyncSynchronized)
+    /// guarded by an `async-std` mutex.
+    pub type ThroughputAsyncSynchronized<T, V = u64, A = SumPerSecond> =
+        crate::async_lock::ThroughputAsyncSynchronized<T, Mutex<super::Throughput<T, V, A>>, V, A>;
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::cell::Cell;
+    use std::option::Option;
+    use std::panic;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Barrier, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use super::TimeSource;
+    use crate::testing::ManualClock;
+
+    use tokio::runtime::Runtime;
+    use tokio::time::{sleep, sleep_until};
+
+    struct FakeInstant {}
+
+    impl super::TimeSource for FakeInstant {
+        fn now() -> Self {
+            FakeInstant {}
+        }
+
+        fn elapsed(&self) -> Duration {
+            Duration::new(10, 0)
+        }
+    }
+
+    struct ZeroTimeFakeInstant {}
+
+    impl super::TimeSource for ZeroTimeFakeInstant {
+        fn now() -> Self {
+            ZeroTimeFakeInstant {}
+        }
+
+        fn elapsed(&self) -> Duration {
+            Duration::new(0, 0)
+        }
+    }
+
+    struct SubMillisecondFakeInstant {}
+
+    impl super::TimeSource for SubMillisecondFakeInstant {
+        fn now() -> Self {
+            SubMillisecondFakeInstant {}
+        }
+
+        fn elapsed(&self) -> Duration {
+            Duration::new(0, 500_000)
+        }
+    }
+
+    struct LongFakeInstant {}
+
+    impl super::TimeSource for LongFakeInstant {
+        fn now() -> Self {
+            LongFakeInstant {}
+        }
+
+        fn elapsed(&self) -> Duration {
+            Duration::new(5_000_000_000, 0)
+        }
+    }
+
+    #[test]
+    fn test_instant_now_minus() {
+        let start = <Instant as super::TimeSource>::now_minus(Duration::from_secs(1));
+        let elapsed = super::TimeSource::elapsed(&start);
+        assert!(elapsed >= Duration::from_secs(1));
+        assert!(elapsed < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_basic() {
+        let mut tp: super::Throughput<Instant> = super::Throughput::new();
+        tp.report(1);
+        tp.report(1);
+
+        let _ = tp.throughput();
+
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(1);
+        tp.report(1);
+
+        assert_approx_eq!(tp.throughput().unwrap(), 0.2);
+    }
+
+    #[test]
+    fn test_u64_sum() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(u64::from(u32::MAX));
+        tp.report(u64::from(u32::MAX));
+
+        assert_eq!(tp.sum, 2 * u64::from(u32::MAX));
+        assert_approx_eq!(tp.throughput().unwrap(), 2.0 * f64::from(u32::MAX) / 10.0);
+    }
+
+    #[test]
+    fn test_saturating_report() {
+        let mut tp: super::Throughput<FakeInstant, u32> = super::Throughput::new();
+        tp.report(u32::MAX);
+        tp.report(1);
+
+        assert_eq!(tp.sum, u32::MAX);
+    }
+
+    #[test]
+    fn test_checked_report() {
+        let mut tp: super::Throughput<FakeInstant, u32> = super::Throughput::new();
+        assert_eq!(Ok(()), tp.checked_report(u32::MAX));
+        assert_eq!(Err(super::OverflowError), tp.checked_report(1));
+        assert_eq!(tp.sum, u32::MAX);
+
+        assert_eq!(
+            super::OverflowError.to_string(),
+            "throughput sum overflowed"
+        );
+
+        let tp: super::ThroughputSynchronized<FakeInstant, u32> =
+            super::ThroughputSynchronized::new();
+        assert!(tp.checked_report(u32::MAX).is_ok());
+        assert!(tp.checked_report(1).is_err());
+    }
+
+    #[test]
+    fn test_checked_report_overflow_untouched() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock, u32> = super::Throughput::builder()
+            .auto_reset(Duration::from_secs(1))
+            .build();
+        assert_eq!(Ok(()), tp.checked_report(u32::MAX));
+        clock.advance(Duration::from_secs(2));
+
+        assert_eq!(Err(super::OverflowError), tp.checked_report(1));
+        assert_eq!(tp.count(), u32::MAX);
+        assert_eq!(tp.event_count(), 1);
+        assert_eq!(tp.completed_window(), None);
+        assert!(tp.is_stalled(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_value_types() {
+        let mut tp: super::Throughput<FakeInstant, u128> = super::Throughput::new();
+        tp.report(1);
+        assert_approx_eq!(tp.throughput().unwrap(), 0.1);
+
+        let mut tp: super::Throughput<FakeInstant, f64> = super::Throughput::new();
+        tp.report(0.5);
+        tp.report(1.5);
+        assert_approx_eq!(tp.throughput().unwrap(), 0.2);
+    }
+
+    #[test]
+    fn test_peek_throughput() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(2);
+
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 0.2);
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 0.2);
+        assert_approx_eq!(tp.throughput().unwrap(), 0.2);
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 0.0);
+
+        let tp: super::ThroughputSynchronized<FakeInstant> = super::ThroughputSynchronized::new();
+        tp.report(1);
+
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 0.1);
+        assert_approx_eq!(tp.throughput().unwrap(), 0.1);
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_report_many_large() {
+        let values = vec![1; 1_000_000];
+
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        for _ in 0..10 {
+            tp.report_many(&values);
+        }
+        assert_eq!(tp.count(), 10_000_000);
+        assert_eq!(tp.event_count(), 10_000_000);
+
+        tp.events = u64::MAX - 1;
+        tp.report_many(&values);
+        tp.report(1);
+        assert_eq!(tp.event_count(), u64::MAX);
+    }
+
+    #[test]
+    fn test_add_assign() {
+        let mut reported: super::Throughput<FakeInstant> = super::Throughput::new();
+        reported.report(10);
+        reported.report(5);
+
+        let mut added: super::Throughput<FakeInstant> = super::Throughput::new();
+        added += 10;
+        added += 5;
+        assert_eq!(added.count(), reported.count());
+        assert_eq!(added.event_count(), reported.event_count());
+        assert_approx_eq!(added.throughput().unwrap(), 1.5);
+    }
+
+    #[test]
+    fn test_extend() {
+        let values: Vec<u32> = vec![1, 2, 3, 4, 5];
+
+        let mut tp: super::Throughput<FakeInstant, u32> = super::Throughput::new();
+        tp.extend(values.clone());
+        tp.extend(&values);
+        assert_eq!(tp.count(), 30);
+        assert_eq!(tp.event_count(), 10);
+
+        let tp: super::Throughput<FakeInstant, u32> = values.into_iter().collect();
+        assert_eq!(tp.count(), 15);
+        assert_eq!(tp.event_count(), 5);
+    }
+
+    #[test]
+    fn test_report_many() {
+        let values = [1, 2, 3, 4, 5];
+
+        let mut individual: super::Throughput<FakeInstant> = super::Throughput::new();
+        for value in &values {
+            individual.report(*value);
+        }
+
+        let mut batched: super::Throughput<FakeInstant> = super::Throughput::new();
+        batched.report_many(&values);
+        assert_eq!(batched.count(), individual.count());
+        assert_eq!(batched.event_count(), individual.event_count());
+
+        let mut iterated: super::Throughput<FakeInstant> = super::Throughput::new();
+        iterated.report_iter(1..=5);
+        assert_eq!(iterated.count(), individual.count());
+        assert_eq!(iterated.event_count(), individual.event_count());
+
+        let tp: super::ThroughputSynchronized<FakeInstant> = super::ThroughputSynchronized::new();
+        tp.report_many(&values);
+        tp.report_iter(values.iter().copied());
+        assert_eq!(tp.count(), 30);
+        assert_eq!(tp.event_count(), 10);
+
+        let tp: super::ThroughputRwLock<FakeInstant> = super::ThroughputRwLock::new();
+        tp.report_many(&values);
+        tp.report_iter(vec![]);
+        assert_eq!(tp.count(), 15);
+    }
+
+    #[test]
+    fn test_measure() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        let result = tp.measure(|| ("done", 42));
+        assert_eq!(result, "done");
+        assert_eq!(tp.count(), 42);
+        assert_eq!(tp.event_count(), 1);
+
+        let tp: super::ThroughputSynchronized<FakeInstant> = super::ThroughputSynchronized::new();
+        let bytes = tp.measure(|| {
+            let bytes = vec![0u8; 16];
+            let len = bytes.len() as u64;
+            (bytes, len)
+        });
+        assert_eq!(bytes.len(), 16);
+        assert_eq!(tp.count(), 16);
+    }
+
+    #[test]
+    fn test_scope() {
+        let tp: super::ThroughputSynchronized<FakeInstant> = super::ThroughputSynchronized::new();
+        {
+            let _guard = tp.scope(3);
+            assert_eq!(tp.count(), 0);
+        }
+        assert_eq!(tp.count(), 3);
+
+        let result = panic::catch_unwind(|| {
+            let _guard = tp.scope(4);
+            panic!("unwinding");
+        });
+        assert!(result.is_err());
+        assert_eq!(tp.count(), 7);
+
+        fn assert_send<S: Send>(_: &S) {}
+        assert_send(&tp.scope(0));
+    }
+
+    #[test]
+    fn test_count() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(1);
+        tp.report(2);
+        tp.report(3);
+
+        assert_eq!(tp.count(), 6);
+        let _ = tp.throughput();
+        assert_eq!(tp.count(), 0);
+
+        let tp: super::ThroughputSynchronized<FakeInstant> = super::ThroughputSynchronized::new();
+        tp.report(1);
+        tp.report(2);
+        tp.report(3);
+
+        assert_eq!(tp.count(), 6);
+    }
+
+    #[test]
+    fn test_event_count() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        for value in &[1, 2, 3, 4] {
+            tp.report(*value);
+        }
+
+        assert_eq!(tp.event_count(), 4);
+        assert_eq!(tp.count(), 10);
+        tp.reset();
+        assert_eq!(tp.event_count(), 0);
+
+        let tp: super::ThroughputSynchronized<FakeInstant> = super::ThroughputSynchronized::new();
+        for value in &[1, 2, 3, 4] {
+            tp.report(*value);
+        }
+
+        assert_eq!(tp.event_count(), 4);
+        assert_eq!(tp.count(), 10);
+    }
+
+    #[test]
+    fn test_average_value() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        assert_eq!(None, tp.average_value());
+
+        tp.report(1);
+        tp.report(4);
+        assert_approx_eq!(tp.average_value().unwrap(), 2.5);
+
+        let tp: super::ThroughputSynchronized<FakeInstant> = super::ThroughputSynchronized::new();
+        assert_eq!(None, tp.average_value());
+
+        tp.report(3);
+        assert_approx_eq!(tp.average_value().unwrap(), 3.0);
+    }
+
+    #[test]
+    fn test_elapsed() {
+        let tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        assert_eq!(tp.elapsed(), Duration::new(10, 0));
+
+        let tp: super::ThroughputSynchronized<FakeInstant> = super::ThroughputSynchronized::new();
+        assert_eq!(tp.elapsed(), Duration::new(10, 0));
+    }
+
+    #[test]
+    fn test_peak() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        assert_eq!(None, tp.peak());
+
+        for value in &[10, 20, 30, 20, 10] {
+            tp.report(*value);
+            let _ = tp.throughput_and_update_peak();
+        }
+
+        assert_approx_eq!(tp.peak().unwrap(), 3.0);
+
+        let mut tp: super::Throughput<ZeroTimeFakeInstant> = super::Throughput::new();
+        tp.report(1);
+        assert_eq!(None, tp.throughput_and_update_peak());
+        assert_eq!(None, tp.peak());
+    }
+
+    #[test]
+    fn test_min() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        assert_eq!(None, tp.min());
+
+        for value in &[30, 20, 10, 20, 30] {
+            tp.report(*value);
+            let _ = tp.throughput_and_update_peak();
+        }
+
+        assert_approx_eq!(tp.min().unwrap(), 1.0);
+        assert_approx_eq!(tp.peak().unwrap(), 3.0);
+
+        tp.reset_extremes();
+        assert_eq!(None, tp.min());
+        assert_eq!(None, tp.peak());
+
+        let mut tp: super::Throughput<ZeroTimeFakeInstant> = super::Throughput::new();
+        let _ = tp.throughput_and_update_peak();
+        assert_eq!(None, tp.min());
+    }
+
+    #[test]
+    fn test_total() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(3);
+        tp.report_many(&[1, 2]);
+        let _ = tp.throughput();
+        tp.checked_report(4).unwrap();
+
+        assert_eq!(tp.count(), 4);
+        assert_eq!(tp.total(), 10);
+
+        tp.reset_total();
+        assert_eq!(tp.total(), 0);
+        assert_eq!(tp.count(), 4);
+
+        let tp: super::ThroughputSynchronized<FakeInstant> = super::ThroughputSynchronized::new();
+        tp.report(5);
+        tp.reset();
+        tp.report(6);
+        assert_eq!(tp.count(), 6);
+        assert_eq!(tp.total(), 11);
+    }
+
+    #[test]
+    fn test_report_delta() {
+        let mut tp: super::Throughput<FakeInstant, i64> = super::Throughput::new();
+        tp.report_delta(5);
+        tp.report_delta(-25);
+        assert_eq!(tp.count(), -20);
+        assert_approx_eq!(tp.throughput().unwrap(), -2.0);
+
+        tp.report_delta(-1);
+        tp.report_delta(11);
+        assert_approx_eq!(tp.throughput().unwrap(), 1.0);
+
+        tp.report_delta(i64::MIN);
+        tp.report_delta(-1);
+        assert_eq!(tp.count(), i64::MIN);
+    }
+
+    #[test]
+    fn test_into_time_source() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock> = super::Throughput::new();
+        tp.report(7);
+        tp.report(3);
+        clock.advance(Duration::from_secs(5));
+
+        let tp: super::Throughput<Instant> = tp.into_time_source();
+        assert_eq!(tp.count(), 10);
+        assert_eq!(tp.event_count(), 2);
+        assert_eq!(tp.total(), 10);
+        assert!(tp.elapsed() < Duration::from_secs(5));
+
+        // the configuration moves along
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::builder()
+            .min_elapsed(Duration::from_secs(1))
+            .skip_first(true)
+            .build();
+        tp.set_scale(2.0);
+        tp.report(5);
+
+        let mut tp: super::Throughput<FakeInstant> = tp.into_time_source();
+        assert_eq!(tp.options.min_elapsed, Duration::from_secs(1));
+        assert_eq!(None, tp.throughput());
+        tp.report(5);
+        assert_approx_eq!(tp.throughput().unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_threshold() {
+        let fired = Arc::new(AtomicBool::new(false));
+
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        let flag = fired.clone();
+        tp.set_threshold(
+            super::Threshold::Below(1.0),
+            Box::new(move |rate| {
+                assert_approx_eq!(rate, 0.5);
+                flag.store(true, Ordering::SeqCst);
+            }),
+        );
+
+        tp.report(10);
+        let _ = tp.throughput();
+        assert!(!fired.load(Ordering::SeqCst));
+
+        tp.report(5);
+        let _ = tp.throughput();
+        assert!(fired.load(Ordering::SeqCst));
+
+        let fired = Arc::new(AtomicBool::new(false));
+
+        let mut tp: super::Throughput<ZeroTimeFakeInstant> = super::Throughput::new();
+        let flag = fired.clone();
+        tp.set_threshold(
+            super::Threshold::Above(0.0),
+            Box::new(move |_| flag.store(true, Ordering::SeqCst)),
+        );
+
+        tp.report(1);
+        assert_eq!(None, tp.throughput());
+        assert!(!fired.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_average_latency() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        assert_eq!(None, tp.average_latency());
+
+        tp.report_with_latency(1, Duration::from_millis(10));
+        tp.report_with_latency(1, Duration::from_millis(30));
+        // plain reports don't dilute the latency
+        tp.report(1);
+        assert_eq!(tp.average_latency(), Some(Duration::from_millis(20)));
+        assert_eq!(tp.count(), 3);
+
+        assert_approx_eq!(tp.throughput().unwrap(), 0.3);
+        assert_eq!(None, tp.average_latency());
+
+        let tp: super::ThroughputSynchronized<FakeInstant> = super::ThroughputSynchronized::new();
+        tp.report_with_latency(1, Duration::from_micros(5));
+        tp.report_with_latency(1, Duration::from_micros(7));
+        assert_eq!(tp.average_latency(), Some(Duration::from_micros(6)));
+    }
+
+    #[test]
+    fn test_log_on_drop() {
+        let logged = Arc::new(Mutex::new(None));
+        let sink = logged.clone();
+        let tp = super::LogOnDrop::new(
+            super::ThroughputSynchronized::<FakeInstant>::new(),
+            move |rate| *sink.lock().unwrap() = Some(rate),
+        );
+        tp.report(100);
+        drop(tp);
+        assert_eq!(*logged.lock().unwrap(), Some(Some(10.0)));
+
+        // a poisoned lock doesn't keep the rate from being passed on
+        let sink = logged.clone();
+        let tp = super::LogOnDrop::new(
+            super::ThroughputSynchronized::<FakeInstant>::new(),
+            move |rate| *sink.lock().unwrap() = Some(rate),
+        );
+        tp.report(50);
+        let _ = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            let _guard = tp.tp_unsynchronized.lock().unwrap();
+            panic!("poisoning the lock");
+        }));
+        assert!(tp.tp_unsynchronized.is_poisoned());
+        drop(tp);
+        assert_eq!(*logged.lock().unwrap(), Some(Some(5.0)));
+    }
+
+    #[test]
+    fn test_is_stalled() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock> = super::Throughput::new();
+        clock.advance(Duration::from_secs(2));
+        assert!(!tp.is_stalled(Duration::from_secs(3)));
+
+        // slow, but not stalled
+        tp.report(1);
+        clock.advance(Duration::from_secs(3));
+        assert!(!tp.is_stalled(Duration::from_secs(3)));
+        tp.reset();
+        clock.advance(Duration::from_secs(1));
+        assert!(tp.is_stalled(Duration::from_secs(3)));
+
+        tp.report(1);
+        assert!(!tp.is_stalled(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn test_pause_resume() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock> = super::Throughput::new();
+        tp.report(10);
+        clock.advance(Duration::from_secs(1));
+
+        tp.pause();
+        assert!(tp.is_paused());
+        clock.advance(Duration::from_secs(8));
+        assert_eq!(tp.elapsed(), Duration::from_secs(1));
+
+        tp.resume();
+        assert!(!tp.is_paused());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(tp.elapsed(), Duration::from_secs(2));
+        assert_approx_eq!(tp.throughput().unwrap(), 5.0);
+
+        // a reset while paused starts a paused window
+        tp.pause();
+        tp.reset();
+        clock.advance(Duration::from_secs(3));
+        tp.resume();
+        tp.report(4);
+        clock.advance(Duration::from_secs(2));
+        assert_approx_eq!(tp.throughput().unwrap(), 2.0);
+    }
+
+    #[test]
+    fn test_skip_first() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::builder()
+            .skip_first(true)
+            .track_peak(true)
+            .build();
+
+        tp.report(1000);
+        assert_eq!(tp.throughput(), None);
+        assert_eq!(tp.count(), 0);
+        assert_eq!(tp.peak(), None);
+
+        tp.report(10);
+        assert_approx_eq!(tp.throughput().unwrap(), 1.0);
+        tp.report(10);
+        assert_approx_eq!(tp.throughput().unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_auto_reset() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock> = super::Throughput::builder()
+            .auto_reset(Duration::from_secs(1))
+            .build();
+
+        tp.report(10);
+        clock.advance(Duration::from_millis(500));
+        tp.report(10);
+        assert_eq!(tp.completed_window(), None);
+        assert_eq!(tp.count(), 20);
+
+        // crossing the period closes the window before adding the value
+        clock.advance(Duration::from_millis(500));
+        tp.report(5);
+        assert_approx_eq!(tp.completed_window().unwrap(), 20.0);
+        assert_eq!(tp.count(), 5);
+        assert_eq!(tp.event_count(), 1);
+
+        clock.advance(Duration::from_millis(2500));
+        tp.report_many(&[1, 2]);
+        assert_approx_eq!(tp.completed_window().unwrap(), 2.0);
+        assert_eq!(tp.count(), 3);
+    }
+
+    thread_local! {
+        static COARSE_READINGS: Cell<u64> = const { Cell::new(0) };
+    }
+
+    // clock with a granularity of 15ms, which advances by 1ms per reading
+    struct CoarseClock(Duration);
+
+    impl CoarseClock {
+        const RESOLUTION: Duration = Duration::from_millis(15);
+
+        fn read() -> Duration {
+            let readings = COARSE_READINGS.with(|readings| {
+                readings.set(readings.get() + 1);
+                readings.get()
+            });
+            Self::RESOLUTION * (readings / 15) as u32
+        }
+    }
+
+    impl super::TimeSource for CoarseClock {
+        fn now() -> Self {
+            CoarseClock(Self::read())
+        }
+
+        fn elapsed(&self) -> Duration {
+            Self::read().saturating_sub(self.0)
+        }
+    }
+
+    #[test]
+    fn test_clock_resolution() {
+        let tp: super::Throughput<CoarseClock> = super::Throughput::new();
+        assert_eq!(tp.clock_resolution(), CoarseClock::RESOLUTION);
+
+        // a clock which never advances has no measurable resolution
+        let tp: super::Throughput<ManualClock> = super::Throughput::new();
+        assert_eq!(tp.clock_resolution(), Duration::ZERO);
+
+        let mut tp: super::Throughput<CoarseClock> = super::Throughput::builder()
+            .reject_below_resolution(true)
+            .build();
+        tp.report(30);
+        // one tick at most, however many readings passed
+        assert!(tp.elapsed() <= CoarseClock::RESOLUTION);
+        assert_eq!(tp.throughput(), None);
+        assert_eq!(tp.count(), 30);
+
+        while tp.elapsed() <= CoarseClock::RESOLUTION {}
+        assert!(tp.throughput().is_some());
+        assert_eq!(tp.count(), 0);
+    }
+
+    #[test]
+    fn test_min_elapsed() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock> = super::Throughput::builder()
+            .min_elapsed(Duration::from_secs(1))
+            .build();
+
+        tp.report(1);
+        clock.advance(Duration::from_micros(10));
+        assert_eq!(tp.peek_throughput(), None);
+        assert_eq!(tp.throughput(), None);
+        // the window is kept
+        assert_eq!(tp.count(), 1);
+
+        tp.report(1);
+        clock.advance(Duration::from_micros(999_990));
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 2.0);
+        assert_approx_eq!(tp.throughput().unwrap(), 2.0);
+        assert_eq!(tp.count(), 0);
+
+        // every other way to read the rate is gated just the same
+        tp.report(1);
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(tp.peek_snapshot().rate, None);
+        assert_eq!(tp.snapshot().rate, None);
+        assert_eq!(tp.sample().0, None);
+        assert_eq!(tp.throughput_over(Duration::from_secs(1)), None);
+        assert_eq!(tp.throughput_at(ManualClock::now()), None);
+        assert_eq!(
+            tp.try_throughput(),
+            Err(super::ThroughputError::BelowMinElapsed)
+        );
+        assert_eq!(tp.count(), 1);
+
+        clock.advance(Duration::from_secs(1));
+        assert!(tp.snapshot().rate.is_some());
+        assert_eq!(tp.count(), 0);
+    }
+
+    #[test]
+    fn test_snapshot_closes_window() {
+        let fired = Arc::new(AtomicBool::new(false));
+        let flag = fired.clone();
+
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::builder()
+            .unit(super::RateUnit::PerMinute)
+            .track_peak(true)
+            .skip_first(true)
+            .threshold(
+                super::Threshold::Above(60.0),
+                Box::new(move |_| flag.store(true, Ordering::SeqCst)),
+            )
+            .build();
+
+        tp.report(10);
+        // the warmup window is discarded
+        assert_eq!(tp.snapshot().rate, None);
+        assert_eq!(tp.count(), 0);
+
+        tp.report(20);
+        let snapshot = tp.snapshot();
+        assert_approx_eq!(snapshot.rate.unwrap(), 2.0);
+        assert_approx_eq!(tp.peak().unwrap(), 120.0);
+        assert!(fired.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_builder() {
+        let fired = Arc::new(AtomicBool::new(false));
+        let flag = fired.clone();
+
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::builder()
+            .unit(super::RateUnit::PerMinute)
+            .track_peak(true)
+            .threshold(
+                super::Threshold::Above(30.0),
+                Box::new(move |_| flag.store(true, Ordering::SeqCst)),
+            )
+            .build();
+        assert_eq!(tp.unit(), super::RateUnit::PerMinute);
+
+        tp.report(1);
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 6.0);
+        assert_approx_eq!(tp.throughput().unwrap(), 6.0);
+        assert!(!fired.load(Ordering::SeqCst));
+
+        tp.report(10);
+        assert_approx_eq!(tp.throughput_per(super::RateUnit::PerSecond).unwrap(), 1.0);
+        assert!(fired.load(Ordering::SeqCst));
+
+        tp.report(2);
+        let _ = tp.throughput();
+        assert_approx_eq!(tp.peak().unwrap(), 60.0);
+
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::builder().build();
+        assert_eq!(tp.unit(), super::RateUnit::PerSecond);
+        tp.report(1);
+        let _ = tp.throughput();
+        assert_eq!(None, tp.peak());
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(1000);
+        assert_eq!(None, tp.histogram());
+
+        tp.record_histogram();
+        tp.report(0);
+        tp.report_many(&[1, 1, 1]);
+        tp.checked_report(1000).unwrap();
+        tp.report(u64::MAX);
+
+        let buckets = tp.histogram().unwrap().buckets();
+        assert_eq!(buckets[0], 1);
+        assert_eq!(buckets[1], 3);
+        assert_eq!(buckets[10], 1);
+        assert_eq!(buckets[64], 1);
+        assert_eq!(buckets.iter().sum::<u64>(), 6);
+
+        let _ = tp.throughput();
+        assert_eq!(
+            tp.histogram().unwrap().buckets(),
+            [0; super::HISTOGRAM_BUCKETS]
+        );
+
+        // the buckets are only allocated when enabled
+        assert!(
+            std::mem::size_of::<super::Throughput<FakeInstant>>()
+                < std::mem::size_of::<crate::ValueHistogram<{ super::HISTOGRAM_BUCKETS }>>()
+        );
+    }
+
+    #[test]
+    fn test_clone() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock> = super::Throughput::new();
+        tp.report(6);
+        clock.advance(Duration::from_secs(2));
+
+        let before = tp.clone();
+        tp.report(4);
+        clock.advance(Duration::from_secs(1));
+
+        assert_eq!(before.count(), 6);
+        assert_eq!(before.event_count(), 1);
+        assert_eq!(before.elapsed(), tp.elapsed());
+        assert_eq!(tp.count() - before.count(), 4);
+        assert_approx_eq!(before.peek_throughput().unwrap(), 2.0);
+    }
+
+    #[test]
+    fn test_report_instant() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock> = super::Throughput::new();
+        assert_eq!(None, tp.report_instant(10));
+
+        clock.advance(Duration::from_secs(2));
+        assert_approx_eq!(tp.report_instant(10).unwrap(), 5.0);
+        assert_eq!(None, tp.report_instant(10));
+
+        clock.advance(Duration::from_millis(500));
+        tp.reset();
+        assert_approx_eq!(tp.report_instant(1).unwrap(), 2.0);
+        assert_eq!(tp.count(), 1);
+    }
+
+    #[test]
+    fn test_try_throughput() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        assert_eq!(Err(super::ThroughputError::NoData), tp.try_throughput());
+        tp.report(5);
+        assert_approx_eq!(tp.try_throughput().unwrap(), 0.5);
+
+        let mut tp: super::Throughput<ZeroTimeFakeInstant> = super::Throughput::new();
+        tp.report(5);
+        assert_eq!(
+            Err(super::ThroughputError::ZeroElapsed),
+            tp.try_throughput()
+        );
+        assert_eq!(tp.count(), 0);
+    }
+
+    #[test]
+    fn test_reset_and_report() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock> = super::Throughput::new();
+        tp.report(6);
+        clock.advance(Duration::from_secs(3));
+
+        assert_approx_eq!(tp.reset_and_report().unwrap(), 2.0);
+        assert_eq!(tp.count(), 0);
+        assert_eq!(tp.elapsed(), Duration::default());
+
+        tp.report(1);
+        clock.advance(Duration::from_secs(1));
+        assert_approx_eq!(tp.reset_and_report().unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_with_state() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock> =
+            super::Throughput::with_state(100, Duration::from_secs(10));
+        assert_eq!(tp.elapsed(), Duration::from_secs(10));
+        assert_eq!(tp.event_count(), 0);
+
+        tp.report(20);
+        clock.advance(Duration::from_secs(2));
+        assert_approx_eq!(tp.try_throughput().unwrap(), 10.0);
+
+        let mut tp: super::Throughput<ManualClock> =
+            super::Throughput::with_state(5, Duration::from_secs(1));
+        assert_approx_eq!(tp.try_throughput().unwrap(), 5.0);
+    }
+
+    #[test]
+    fn test_throughput_rounded() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock> = super::Throughput::new();
+        for (decimals, expected) in &[(0, 7.0), (2, 6.67), (4, 6.6667)] {
+            tp.report(20);
+            clock.advance(Duration::from_secs(3));
+            assert_eq!(tp.throughput_rounded(*decimals).unwrap(), *expected);
+        }
+
+        tp.report(10);
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(tp.throughput_rounded(2).unwrap(), 3.33);
+
+        let mut tp: super::Throughput<ZeroTimeFakeInstant> = super::Throughput::new();
+        assert_eq!(None, tp.throughput_rounded(2));
+    }
+
+    #[test]
+    fn test_throughput_per() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(1);
+        assert_approx_eq!(tp.throughput_per(super::RateUnit::PerSecond).unwrap(), 0.1);
+
+        tp.report(1);
+        assert_approx_eq!(tp.throughput_per(super::RateUnit::PerMinute).unwrap(), 6.0);
+
+        tp.report(1);
+        assert_approx_eq!(tp.throughput_per(super::RateUnit::PerHour).unwrap(), 360.0);
+
+        let mut tp: super::Throughput<ZeroTimeFakeInstant> = super::Throughput::new();
+        assert_eq!(None, tp.throughput_per(super::RateUnit::PerMinute));
+    }
+
+    #[test]
+    fn test_duration_since() {
+        let earlier = Instant::now();
+        let later = earlier + Duration::from_millis(1500);
+        assert_eq!(
+            TimeSource::duration_since(&later, &earlier),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(
+            TimeSource::duration_since(&earlier, &later),
+            Duration::default()
+        );
+
+        // the default compares the elapsed times, which stand still for fakes
+        assert_eq!(
+            FakeInstant::now().duration_since(&FakeInstant::now()),
+            Duration::default()
+        );
+    }
+
+    #[test]
+    fn test_set_scale() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.set_scale(0.5);
+        tp.report(3);
+
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 0.15);
+        assert_approx_eq!(tp.peek_snapshot().rate.unwrap(), 0.15);
+        assert_eq!(tp.count(), 3);
+        assert_approx_eq!(tp.throughput().unwrap(), 0.15);
+
+        // the scale is kept across windows
+        tp.report(3);
+        assert_approx_eq!(tp.throughput().unwrap(), 0.15);
+    }
+
+    #[test]
+    fn test_has_data() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        assert!(!tp.has_data());
+        tp.report(0);
+        assert!(tp.has_data());
+        assert!(tp.throughput().is_some());
+        assert!(!tp.has_data());
+
+        let tp: super::ThroughputSynchronized<FakeInstant> = super::ThroughputSynchronized::new();
+        assert!(!tp.has_data());
+        tp.report(1);
+        assert!(tp.has_data());
+
+        let tp: super::ThroughputRwLock<FakeInstant> = super::ThroughputRwLock::new();
+        assert!(!tp.has_data());
+        tp.report(1);
+        assert!(tp.has_data());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_has_data() {
+        let tp: super::tokio_async::ThroughputAsyncSynchronized<FakeInstant> =
+            super::tokio_async::ThroughputAsyncSynchronized::new();
+        assert!(!tp.has_data().await);
+        tp.report(1).await;
+        assert!(tp.has_data().await);
+    }
+
+    #[test]
+    fn test_rebase_window() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock> = super::Throughput::new();
+        tp.report(30);
+        clock.advance(Duration::from_secs(10));
+
+        tp.rebase_window();
+        assert_eq!(tp.elapsed(), Duration::default());
+        assert_eq!(tp.count(), 30);
+
+        clock.advance(Duration::from_secs(3));
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 10.0);
+
+        let tp: super::ThroughputSynchronized<ManualClock> = super::ThroughputSynchronized::new();
+        tp.report(30);
+        clock.advance(Duration::from_secs(10));
+        tp.rebase_window();
+        clock.advance(Duration::from_secs(2));
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 15.0);
+    }
+
+    #[test]
+    fn test_clear_count() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock> = super::Throughput::new();
+        tp.report(30);
+        clock.advance(Duration::from_secs(2));
+
+        tp.clear_count();
+        assert_eq!(tp.count(), 0);
+        assert_eq!(tp.event_count(), 0);
+        assert_eq!(tp.elapsed(), Duration::from_secs(2));
+
+        tp.report(10);
+        clock.advance(Duration::from_secs(3));
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 2.0);
+
+        let tp: super::ThroughputRwLock<ManualClock> = super::ThroughputRwLock::new();
+        tp.report(30);
+        clock.advance(Duration::from_secs(2));
+        tp.clear_count();
+        tp.report(10);
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 5.0);
+    }
+
+    #[test]
+    fn test_throughput_at() {
+        let clock = ManualClock::now();
+        let mut tp: super::Throughput<ManualClock> = super::Throughput::new();
+        tp.report(30);
+        clock.advance(Duration::from_secs(2));
+        let now = ManualClock::now();
+
+        // the clock moving on doesn't affect a rate at an explicit instant
+        clock.advance(Duration::from_secs(8));
+        assert_approx_eq!(tp.throughput_at(now).unwrap(), 15.0);
+
+        // the new window started at `now`
+        tp.report(40);
+        assert_approx_eq!(tp.throughput_at(ManualClock::now()).unwrap(), 5.0);
+        assert_eq!(tp.throughput_at(ManualClock::now()), None);
+
+        tp.report(10);
+        clock.advance(Duration::from_secs(1));
+        tp.pause();
+        clock.advance(Duration::from_secs(3));
+        assert_approx_eq!(tp.throughput_at(ManualClock::now()).unwrap(), 10.0);
+    }
+
+    #[test]
+    fn test_clock_going_backwards() {
+        struct WallClock(std::time::SystemTime);
+
+        impl super::TimeSource for WallClock {
+            fn now() -> Self {
+                // as if the clock was set back by an hour since
+                WallClock(std::time::SystemTime::now() + Duration::from_secs(3600))
+            }
+
+            fn elapsed(&self) -> Duration {
+                self.0.elapsed().unwrap_or_default()
+            }
+        }
+
+        let mut tp: super::Throughput<WallClock> = super::Throughput::new();
+        tp.report(1);
+        assert_eq!(tp.peek_throughput(), None);
+        assert_eq!(tp.throughput(), None);
+
+        // a tiny elapsed time can't turn a huge sum into an infinite rate
+        let mut tp: super::Throughput<crate::testing::FnTimeSource, f64> =
+            super::Throughput::with_fn(|| Duration::from_nanos(1));
+        tp.report(f64::MAX);
+        assert_eq!(tp.throughput(), None);
+        tp.report(1.0);
+        assert_approx_eq!(tp.throughput().unwrap(), 1e9);
+    }
+
+    #[test]
+    fn test_throughput_bits() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(1_250_000_000);
+        let bits = tp.throughput_bits().unwrap();
+        assert_approx_eq!(bits, 1e9);
+        assert_eq!(crate::format::format_bits_per_sec(bits), "1.0 Gbit/s");
+        assert_eq!(tp.count(), 0);
+
+        let mut tp: super::Throughput<ZeroTimeFakeInstant> = super::Throughput::new();
+        assert_eq!(None, tp.throughput_bits());
+    }
+
+    #[test]
+    fn test_name() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        assert_eq!(tp.name(), None);
+        tp.report(25);
+        assert_eq!(tp.to_string(), "2.5 units/s over 10.00s");
+
+        tp.set_name("ingest");
+        assert_eq!(tp.name(), Some("ingest"));
+        assert_eq!(tp.to_string(), "ingest: 2.5 units/s over 10.00s");
+        // formatting doesn't reset the window
+        assert_eq!(tp.count(), 25);
+
+        let tp: super::Throughput<FakeInstant> = super::Throughput::builder().name("db").build();
+        assert_eq!(tp.name(), Some("db"));
+        let tp: super::Throughput<ManualClock> = tp.into_time_source();
+        assert_eq!(tp.name(), Some("db"));
+    }
+
+    #[test]
+    fn test_throughput_f32() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(1_234_567_891);
+        let rate = tp.peek_throughput().unwrap();
+        let rate_f32 = tp.throughput_f32().unwrap();
+        assert!((f64::from(rate_f32) - rate).abs() <= rate * f64::from(f32::EPSILON));
+        assert_eq!(tp.count(), 0);
+
+        let mut tp: super::Throughput<ZeroTimeFakeInstant> = super::Throughput::new();
+        assert_eq!(None, tp.throughput_f32());
+    }
+
+    #[test]
+    fn test_throughput_over() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(30);
+        assert_approx_eq!(tp.throughput_over(Duration::from_secs(3)).unwrap(), 10.0);
+        assert_eq!(tp.count(), 0);
+
+        let mut tp: super::Throughput<ZeroTimeFakeInstant> = super::Throughput::new();
+        tp.report(30);
+        assert_approx_eq!(
+            tp.throughput_over(Duration::from_millis(500)).unwrap(),
+            60.0
+        );
+        tp.report(30);
+        assert_eq!(None, tp.throughput_over(Duration::default()));
+        assert_eq!(tp.count(), 0);
+    }
+
+    #[test]
+    fn test_throughput_over_closes_window() {
+        let fired = Arc::new(AtomicBool::new(false));
+        let flag = fired.clone();
+
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::builder()
+            .track_peak(true)
+            .skip_first(true)
+            .threshold(
+                super::Threshold::Above(5.0),
+                Box::new(move |_| flag.store(true, Ordering::SeqCst)),
+            )
+            .build();
+
+        tp.report(30);
+        // the warmup window is discarded
+        assert_eq!(tp.throughput_over(Duration::from_secs(3)), None);
+        assert_eq!(tp.peak(), None);
+        assert!(!fired.load(Ordering::SeqCst));
+
+        tp.report(30);
+        assert_approx_eq!(tp.throughput_over(Duration::from_secs(3)).unwrap(), 10.0);
+        assert_approx_eq!(tp.peak().unwrap(), 10.0);
+        assert!(fired.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(12345);
+
+        let snapshot = tp.snapshot();
+        assert_eq!(snapshot.sum, 12345);
+        assert_eq!(snapshot.events, 1);
+        assert_eq!(snapshot.elapsed, Duration::new(10, 0));
+        assert_approx_eq!(snapshot.rate.unwrap(), 1234.5);
+        assert_eq!(snapshot.to_string(), "1234.5 units/s over 10.00s");
+        assert_eq!(tp.count(), 0);
 
-    pub fn reset(&self) {
-        self.tp_unsynchronized.lock().unwrap().reset();
-    }
+        let mut tp: super::Throughput<ZeroTimeFakeInstant> = super::Throughput::new();
+        tp.report(1);
 
-    pub fn throughput(&self) -> Option<f64> {
-        self.tp_unsynchronized.lock().unwrap().throughput()
+        assert_eq!(tp.snapshot().to_string(), "n/a over 0.00s");
     }
-}
 
-impl<T: TimeSource> Default for ThroughputSynchronized<T> {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+    #[test]
+    fn test_snapshot_eq() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(12345);
 
-#[cfg(feature = "async")]
-pub mod tokio_async {
-    use tokio::sync::Mutex;
+        let snapshot = tp.peek_snapshot();
+        assert_eq!(snapshot, tp.peek_snapshot());
+        assert_eq!(snapshot, snapshot.clone());
+        assert!(snapshot.approx_eq(&tp.peek_snapshot(), 1e-9));
+
+        tp.report(1);
+        assert_ne!(snapshot, tp.peek_snapshot());
+        assert!(!snapshot.approx_eq(&tp.peek_snapshot(), 1e-9));
 
-    #[derive(Debug)]
-    pub struct ThroughputAsyncSynchronized<T: super::TimeSource> {
-        tp_unsynchronized: Mutex<super::Throughput<T>>,
+        // equality ignores the rate, approx_eq doesn't
+        let mut other = snapshot.clone();
+        other.rate = Some(1000.0);
+        assert_eq!(snapshot, other);
+        assert!(!snapshot.approx_eq(&other, 1.0));
+        other.rate = None;
+        assert!(!snapshot.approx_eq(&other, 1.0));
     }
 
-    impl<T: super::TimeSource> ThroughputAsyncSynchronized<T> {
-        pub fn new() -> Self {
-            Self {
-                tp_unsynchronized: Mutex::new(super::Throughput::new()),
-            }
-        }
+    #[test]
+    fn test_report_struct() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(12345);
 
-        pub async fn report(&self, value: u32) {
-            self.tp_unsynchronized.lock().await.report(value);
-        }
+        let report = super::ThroughputReport::from_peek(&tp);
+        assert_eq!(report.sum, 12345);
+        assert_eq!(report.elapsed_ms, 10_000);
+        assert_approx_eq!(report.rate.unwrap(), 1234.5);
+        assert_eq!(tp.count(), 12345);
 
-        pub async fn reset(&self) {
-            self.tp_unsynchronized.lock().await.reset();
-        }
+        let report = super::ThroughputReport::from(&mut tp);
+        assert_eq!(report.sum, 12345);
+        assert_eq!(report.elapsed_ms, 10_000);
+        assert_approx_eq!(report.rate.unwrap(), 1234.5);
+        assert_eq!(tp.count(), 0);
 
-        pub async fn throughput(&self) -> Option<f64> {
-            self.tp_unsynchronized.lock().await.throughput()
-        }
+        let mut tp: super::Throughput<SubMillisecondFakeInstant> = super::Throughput::new();
+        tp.report(1);
+        let report: super::ThroughputReport = (&mut tp).into();
+        assert_eq!(report.elapsed_ms, 0);
+        assert_approx_eq!(report.rate.unwrap(), 2000.0);
     }
 
-    impl<T: super::TimeSource> Default for ThroughputAsyncSynchronized<T> {
-        fn default() -> Self {
-            Self::new()
-        }
+    #[test]
+    fn test_sample() {
+        let mut tp: super::Throughput<SubMillisecondFakeInstant> = super::Throughput::new();
+        tp.report(3);
+
+        let (rate, elapsed, sum) = tp.sample();
+        assert_eq!(sum, 3);
+        assert_eq!(elapsed, Duration::new(0, 500_000));
+        assert_approx_eq!(rate.unwrap(), sum as f64 / elapsed.as_secs_f64());
+        assert_eq!(tp.count(), 0);
+
+        let mut tp: super::Throughput<ZeroTimeFakeInstant> = super::Throughput::new();
+        tp.report(3);
+        assert_eq!(tp.sample(), (None, Duration::default(), 3));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use assert_approx_eq::assert_approx_eq;
+    #[test]
+    fn test_state() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(3);
+        tp.report(5);
 
-    use std::option::Option;
-    use std::sync::{Arc, Barrier, Mutex};
-    use std::thread;
-    use std::time::{Duration, Instant};
+        let state = tp.to_state();
+        assert_eq!(
+            state,
+            super::ThroughputState {
+                sum: 8,
+                events: 2,
+                elapsed: Duration::new(10, 0),
+            }
+        );
 
-    use tokio::runtime::Runtime;
-    use tokio::time::{sleep, sleep_until};
+        let clock = ManualClock::now();
+        let tp: super::Throughput<ManualClock> = super::Throughput::from_state(state);
+        assert_eq!(tp.count(), 8);
+        assert_eq!(tp.event_count(), 2);
+        assert_eq!(tp.elapsed(), Duration::new(10, 0));
+        clock.advance(Duration::from_secs(10));
+        assert_approx_eq!(tp.peek_throughput().unwrap(), 0.4);
 
-    struct FakeInstant {}
+        // a source which cannot back-date restarts the elapsed time
+        let tp: super::Throughput<ZeroTimeFakeInstant> = super::Throughput::from_state(state);
+        assert_eq!(tp.count(), 8);
+        assert_eq!(tp.elapsed(), Duration::new(0, 0));
+    }
 
-    impl super::TimeSource for FakeInstant {
-        fn now() -> Self {
-            FakeInstant {}
-        }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_state_serde_round_trip() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(42);
 
-        fn elapsed(&self) -> Duration {
-            Duration::new(10, 0)
-        }
+        let json = serde_json::to_string(&tp.to_state()).unwrap();
+        let state: super::ThroughputState = serde_json::from_str(&json).unwrap();
+        assert_eq!(state, tp.to_state());
+
+        let tp: super::Throughput<FakeInstant> = super::Throughput::from_state(state);
+        assert_eq!(tp.count(), 42);
+        assert_eq!(tp.event_count(), 1);
     }
 
-    struct ZeroTimeFakeInstant {}
+    #[test]
+    fn test_rate_unit_hash() {
+        let units: std::collections::HashSet<super::RateUnit> = [
+            super::RateUnit::PerSecond,
+            super::RateUnit::PerMinute,
+            super::RateUnit::PerHour,
+            super::RateUnit::PerSecond,
+        ]
+        .iter()
+        .copied()
+        .collect();
 
-    impl super::TimeSource for ZeroTimeFakeInstant {
-        fn now() -> Self {
-            ZeroTimeFakeInstant {}
-        }
+        assert_eq!(units.len(), 3);
+        assert!(units.contains(&super::RateUnit::PerMinute));
+    }
 
-        fn elapsed(&self) -> Duration {
-            Duration::new(0, 0)
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_rate_unit_serde_round_trip() {
+        for unit in &[
+            super::RateUnit::PerSecond,
+            super::RateUnit::PerMinute,
+            super::RateUnit::PerHour,
+        ] {
+            let json = serde_json::to_string(unit).unwrap();
+            let deserialized: super::RateUnit = serde_json::from_str(&json).unwrap();
+            assert_eq!(deserialized, *unit);
         }
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_basic() {
-        let mut tp: super::Throughput<Instant> = super::Throughput::new();
-        tp.report(1);
+    fn test_snapshot_serde_round_trip() {
+        let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
+        tp.report(12345);
+        let snapshot = tp.peek_snapshot();
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let deserialized: super::Snapshot = serde_json::from_str(&json).unwrap();
+        assert!(deserialized.approx_eq(&snapshot, 1e-9));
+
+        let mut tp: super::Throughput<ZeroTimeFakeInstant> = super::Throughput::new();
         tp.report(1);
+        let snapshot = tp.peek_snapshot();
 
-        tp.throughput();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let deserialized: super::Snapshot = serde_json::from_str(&json).unwrap();
+        assert!(deserialized.approx_eq(&snapshot, 0.0));
+    }
 
+    #[test]
+    fn test_debug() {
         let mut tp: super::Throughput<FakeInstant> = super::Throughput::new();
-        tp.report(1);
-        tp.report(1);
+        tp.report(3);
+        assert!(format!("{:?}", tp).contains("sum: 3"));
 
-        assert_approx_eq!(tp.throughput().unwrap(), 0.2);
+        let tp: super::ThroughputSynchronized<FakeInstant> = super::ThroughputSynchronized::new();
+        tp.report(5);
+        let debug = format!("{:?}", tp);
+        assert!(debug.contains("sum: 5"));
+        assert!(debug.contains("poisoned: false"));
+
+        let _guard = tp.tp_unsynchronized.lock().unwrap();
+        assert!(format!("{:?}", tp).contains("<locked>"));
     }
 
     #[test]
@@ -193,6 +3409,22 @@ mod tests {
         assert_eq!(None, tp.throughput());
     }
 
+    #[test]
+    fn test_sub_millisecond() {
+        let mut tp: super::Throughput<SubMillisecondFakeInstant> = super::Throughput::new();
+        tp.report(1);
+
+        assert_approx_eq!(tp.throughput().unwrap(), 2000.0);
+    }
+
+    #[test]
+    fn test_long_window() {
+        let mut tp: super::Throughput<LongFakeInstant> = super::Throughput::new();
+        tp.report(10_000_000_000);
+
+        assert_approx_eq!(tp.throughput().unwrap(), 2.0);
+    }
+
     #[test]
     fn test_in_threads() {
         let tp: Arc<Mutex<super::Throughput<Instant>>> =
@@ -208,7 +3440,7 @@ mod tests {
         let t2 = {
             let tp2 = tp;
             thread::spawn(move || {
-                tp2.lock().unwrap().throughput();
+                let _ = tp2.lock().unwrap().throughput();
             })
         };
 
@@ -217,6 +3449,8 @@ mod tests {
     }
 
     #[test]
+    // the rebindings spell out what moves into each thread
+    #[allow(clippy::redundant_locals)]
     fn test_tp_synchronized_in_threads() {
         let tp: Arc<super::ThroughputSynchronized<FakeInstant>> =
             Arc::new(super::ThroughputSynchronized::new());
@@ -244,6 +3478,233 @@ mod tests {
         assert_approx_eq!(t2.join().unwrap().unwrap(), 0.1);
     }
 
+    #[test]
+    fn test_tp_synchronized_snapshot_in_threads() {
+        let tp: Arc<super::ThroughputSynchronized<FakeInstant>> =
+            Arc::new(super::ThroughputSynchronized::new());
+
+        let writers: Vec<_> = (0..4)
+            .map(|_| {
+                let tp = tp.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        tp.report_many(&[1, 2]);
+                    }
+                })
+            })
+            .collect();
+
+        for _ in 0..1000 {
+            let snapshot = tp.snapshot();
+            assert_eq!(snapshot.sum * 2, snapshot.events * 3);
+        }
+
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let snapshot = tp.snapshot();
+        assert_eq!(snapshot.sum, 12000);
+        assert_eq!(snapshot.events, 8000);
+        assert_approx_eq!(snapshot.rate.unwrap(), 1200.0);
+        assert_eq!(tp.count(), 12000);
+    }
+
+    #[test]
+    fn test_poisoned_recovery() {
+        let tp: Arc<super::ThroughputSynchronized<FakeInstant>> =
+            Arc::new(super::ThroughputSynchronized::new());
+        tp.report(1);
+
+        let poisoner = {
+            let tp = tp.clone();
+            thread::spawn(move || {
+                let _guard = tp.tp_unsynchronized.lock().unwrap();
+                panic!("poisoning the lock");
+            })
+        };
+        assert!(poisoner.join().is_err());
+        assert!(tp.tp_unsynchronized.is_poisoned());
+
+        tp.report(2);
+        assert_eq!(tp.count(), 3);
+        assert_approx_eq!(tp.throughput().unwrap(), 0.3);
+
+        let tp: Arc<super::ThroughputRwLock<FakeInstant>> =
+            Arc::new(super::ThroughputRwLock::new());
+        let poisoner = {
+            let tp = tp.clone();
+            thread::spawn(move || {
+                let _guard = tp.tp_unsynchronized.write().unwrap();
+                panic!("poisoning the lock");
+            })
+        };
+        assert!(poisoner.join().is_err());
+
+        tp.report(4);
+        assert_eq!(tp.count(), 4);
+        assert_approx_eq!(tp.throughput().unwrap(), 0.4);
+    }
+
+    #[test]
+    fn test_new_lazy() {
+        let clock = ManualClock::now();
+        static TP: super::LazyThroughputSynchronized<ManualClock> =
+            super::ThroughputSynchronized::new_lazy();
+
+        // the window starts on first use, not at construction
+        clock.advance(Duration::from_secs(5));
+        TP.report(10);
+        clock.advance(Duration::from_secs(2));
+        TP.report(10);
+
+        assert_eq!(TP.count(), 20);
+        assert_approx_eq!(TP.throughput().unwrap(), 10.0);
+    }
+
+    #[test]
+    fn test_into_inner() {
+        let tp: super::ThroughputSynchronized<FakeInstant> = super::ThroughputSynchronized::new();
+        tp.report(3);
+        assert_eq!(tp.into_inner().count(), 3);
+
+        let tp: super::ThroughputRwLock<FakeInstant> = super::ThroughputRwLock::new();
+        tp.report(4);
+        assert_eq!(tp.into_inner().count(), 4);
+
+        let tp: Arc<super::ThroughputSynchronized<FakeInstant>> =
+            Arc::new(super::ThroughputSynchronized::new());
+        tp.report(5);
+        let poisoner = {
+            let tp = tp.clone();
+            thread::spawn(move || {
+                let _guard = tp.tp_unsynchronized.lock().unwrap();
+                panic!("poisoning the lock");
+            })
+        };
+        assert!(poisoner.join().is_err());
+
+        let tp = Arc::try_unwrap(tp).unwrap().into_inner();
+        assert_eq!(tp.count(), 5);
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut tp: super::ThroughputSynchronized<FakeInstant> =
+            super::ThroughputSynchronized::new();
+        tp.get_mut().report(3);
+        tp.report(4);
+        assert_eq!(tp.count(), 7);
+        assert_eq!(tp.get_mut().event_count(), 2);
+
+        let mut tp: super::ThroughputRwLock<FakeInstant> = super::ThroughputRwLock::new();
+        tp.get_mut().report(3);
+        assert_eq!(tp.count(), 3);
+
+        let mut tp: Arc<super::ThroughputSynchronized<FakeInstant>> =
+            Arc::new(super::ThroughputSynchronized::new());
+        let poisoner = {
+            let tp = tp.clone();
+            thread::spawn(move || {
+                let _guard = tp.tp_unsynchronized.lock().unwrap();
+                panic!("poisoning the lock");
+            })
+        };
+        assert!(poisoner.join().is_err());
+
+        Arc::get_mut(&mut tp).unwrap().get_mut().report(5);
+        assert_eq!(tp.count(), 5);
+    }
+
+    #[test]
+    fn test_try_report() {
+        let tp: Arc<super::ThroughputSynchronized<FakeInstant>> =
+            Arc::new(super::ThroughputSynchronized::new());
+        assert!(tp.try_report(1));
+
+        let locked = Arc::new(Barrier::new(2));
+        let done = Arc::new(Barrier::new(2));
+        let holder = {
+            let tp = tp.clone();
+            let locked = locked.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                let _guard = tp.tp_unsynchronized.lock().unwrap();
+                locked.wait();
+                done.wait();
+            })
+        };
+
+        locked.wait();
+        assert!(!tp.try_report(2));
+        done.wait();
+        holder.join().unwrap();
+
+        assert!(tp.try_report(3));
+        assert_eq!(tp.count(), 4);
+
+        let tp: super::ThroughputRwLock<FakeInstant> = super::ThroughputRwLock::new();
+        assert!(tp.try_report(1));
+        let _guard = tp.tp_unsynchronized.read().unwrap();
+        assert!(!tp.try_report(1));
+    }
+
+    #[test]
+    fn test_tp_rwlock_in_threads() {
+        let tp: Arc<super::ThroughputRwLock<FakeInstant>> =
+            Arc::new(super::ThroughputRwLock::new());
+
+        let writers: Vec<_> = (0..2)
+            .map(|_| {
+                let tp = tp.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        tp.report(1);
+                    }
+                })
+            })
+            .collect();
+
+        let readers: Vec<_> = (0..8)
+            .map(|_| {
+                let tp = tp.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        assert!(tp.peek_throughput().unwrap() <= 200.0);
+                        assert!(tp.count() <= 2000);
+                    }
+                })
+            })
+            .collect();
+
+        for t in writers.into_iter().chain(readers) {
+            t.join().unwrap();
+        }
+
+        assert_eq!(tp.count(), 2000);
+        assert_eq!(tp.event_count(), 2000);
+        assert_approx_eq!(tp.throughput().unwrap(), 200.0);
+        assert_eq!(tp.count(), 0);
+    }
+
+    #[test]
+    fn test_combined_throughput() {
+        let clock = ManualClock::now();
+        assert_eq!(None, super::combined_throughput::<ManualClock, u64>(&[]));
+
+        let mut tp1: super::Throughput<ManualClock> = super::Throughput::new();
+        clock.advance(Duration::from_secs(2));
+        let mut tp2: super::Throughput<ManualClock> = super::Throughput::new();
+        assert_eq!(None, super::combined_throughput(&[&tp2]));
+
+        tp1.report(8);
+        tp2.report(4);
+        clock.advance(Duration::from_secs(2));
+
+        assert_approx_eq!(super::combined_throughput(&[&tp1, &tp2]).unwrap(), 3.0);
+        assert_approx_eq!(super::combined_throughput(&[&tp2]).unwrap(), 2.0);
+    }
+
     #[test]
     fn test_delay() {
         let rt = Runtime::new().unwrap();
@@ -270,4 +3731,161 @@ mod tests {
 
         assert_approx_eq!(tp.throughput().await.unwrap(), 0.2);
     }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_interval_reporter() {
+        let tp = Arc::new(super::tokio_async::ThroughputAsyncSynchronized::<Instant>::new());
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sender = Mutex::new(sender);
+
+        let handle = super::tokio_async::spawn_interval_reporter(
+            Arc::clone(&tp),
+            Duration::from_millis(10),
+            move |rate| sender.lock().unwrap().send(rate).unwrap(),
+        );
+
+        for _ in 0..10 {
+            tp.report(1).await;
+            sleep(tokio::time::Duration::from_millis(10)).await;
+        }
+
+        // the reporter stops once it holds the last reference
+        drop(tp);
+        handle.await.unwrap();
+
+        let rates: Vec<Option<f64>> = receiver.try_iter().collect();
+        assert!(rates.len() >= 3, "{:?}", rates);
+        assert!(rates.iter().all(|rate| rate.is_some()));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_interval_reporter_abort() {
+        let tp = Arc::new(super::tokio_async::ThroughputAsyncSynchronized::<Instant>::new());
+        let handle = super::tokio_async::spawn_interval_reporter(
+            Arc::clone(&tp),
+            Duration::from_millis(10),
+            |_| {},
+        );
+
+        handle.abort();
+        assert!(handle.await.unwrap_err().is_cancelled());
+        assert_eq!(Arc::strong_count(&tp), 1);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_peek_throughput() {
+        let tp: super::tokio_async::ThroughputAsyncSynchronized<FakeInstant> =
+            super::tokio_async::ThroughputAsyncSynchronized::new();
+        tp.report(1).await;
+
+        assert_approx_eq!(tp.peek_throughput().await.unwrap(), 0.1);
+        assert_approx_eq!(tp.throughput().await.unwrap(), 0.1);
+        assert_approx_eq!(tp.peek_throughput().await.unwrap(), 0.0);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_count() {
+        let tp: super::tokio_async::ThroughputAsyncSynchronized<FakeInstant> =
+            super::tokio_async::ThroughputAsyncSynchronized::new();
+        tp.report(1).await;
+        tp.report(2).await;
+        tp.report(3).await;
+
+        assert_eq!(tp.count().await, 6);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_event_count() {
+        let tp: super::tokio_async::ThroughputAsyncSynchronized<FakeInstant> =
+            super::tokio_async::ThroughputAsyncSynchronized::new();
+        for value in &[1, 2, 3, 4] {
+            tp.report(*value).await;
+        }
+
+        assert_eq!(tp.event_count().await, 4);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_average_value() {
+        let tp: super::tokio_async::ThroughputAsyncSynchronized<FakeInstant> =
+            super::tokio_async::ThroughputAsyncSynchronized::new();
+        assert_eq!(None, tp.average_value().await);
+
+        tp.report(2).await;
+        tp.report(4).await;
+        assert_approx_eq!(tp.average_value().await.unwrap(), 3.0);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_elapsed() {
+        let tp: super::tokio_async::ThroughputAsyncSynchronized<FakeInstant> =
+            super::tokio_async::ThroughputAsyncSynchronized::new();
+
+        assert_eq!(tp.elapsed().await, Duration::new(10, 0));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_snapshot() {
+        let tp: super::tokio_async::ThroughputAsyncSynchronized<FakeInstant> =
+            super::tokio_async::ThroughputAsyncSynchronized::new();
+        tp.report(12345).await;
+
+        let snapshot = tp.snapshot().await;
+        assert_eq!(snapshot.sum, 12345);
+        assert_eq!(snapshot.events, 1);
+        assert_eq!(snapshot.elapsed, Duration::new(10, 0));
+        assert_approx_eq!(snapshot.rate.unwrap(), 1234.5);
+
+        // like the other accessors, snapshots don't reset the window
+        assert_eq!(tp.count().await, 12345);
+        assert_approx_eq!(tp.peek_throughput().await.unwrap(), 1234.5);
+        assert_eq!(tp.count().await, 12345);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_debug() {
+        let tp: super::tokio_async::ThroughputAsyncSynchronized<FakeInstant> =
+            super::tokio_async::ThroughputAsyncSynchronized::new();
+        tp.report(7).await;
+
+        assert!(format!("{:?}", tp).contains("sum: 7"));
+    }
+
+    #[cfg(feature = "async-std")]
+    #[test]
+    fn test_async_std_tp() {
+        let tp: super::async_std_async::ThroughputAsyncSynchronized<FakeInstant> =
+            super::async_std_async::ThroughputAsyncSynchronized::new();
+
+        async_std::task::block_on(async {
+            tp.report(1).await;
+            tp.report(2).await;
+
+            assert_eq!(tp.count().await, 3);
+            assert_eq!(tp.event_count().await, 2);
+            assert!(format!("{:?}", tp).contains("sum: 3"));
+            assert_approx_eq!(tp.peek_throughput().await.unwrap(), 0.3);
+            assert_approx_eq!(tp.throughput().await.unwrap(), 0.3);
+            assert_eq!(tp.count().await, 0);
+        });
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_checked_report() {
+        let tp: super::tokio_async::ThroughputAsyncSynchronized<FakeInstant, u32> =
+            super::tokio_async::ThroughputAsyncSynchronized::new();
+
+        assert!(tp.checked_report(u32::MAX).await.is_ok());
+        assert!(tp.checked_report(1).await.is_err());
+    }
 }
@@ -0,0 +1,163 @@
+use std::fmt;
+use std::option::Option;
+
+use ::tracing::Level;
+
+use crate::aggregate::Aggregator;
+use crate::tp::{Accumulate, Throughput, TimeSource};
+
+macro_rules! throughput_event {
+    ($level:expr, $name:expr, $rate:expr, $snapshot:expr) => {
+        ::tracing::event!(
+            target: "tp",
+            $level,
+            name = $name,
+            rate = $rate,
+            sum = ?$snapshot.sum,
+            elapsed_ms = $snapshot.elapsed.as_millis() as u64,
+        )
+    };
+}
+
+impl<T: TimeSource, V: Accumulate + fmt::Debug, A: Aggregator<V>> Throughput<T, V, A> {
+    /// Computes the rate of the current window, starts a new one and emits
+    /// a `tracing` event with the fields `rate`, `sum` and `elapsed_ms`.
+    /// The rate is the one [`throughput`](Throughput::throughput) returns,
+    /// i.e. in the configured [unit](Throughput::unit).
+    ///
+    /// `tracing` requires event targets to be known at compile time, so the
+    /// event is emitted with the target `tp` and the `name` field tells the
+    /// throughputs apart: it holds the [name](Throughput::name) of the
+    /// throughput, or `target` if it has none.
+    pub fn log_throughput(&mut self, target: &str, level: Level) -> Option<f64> {
+        let snapshot = self.snapshot();
+        // the snapshot is per second, like its `Display` output
+        let rate = snapshot.rate.map(|rate| rate * self.unit().seconds());
+        let name = self.name().unwrap_or(target);
+        match level {
+            Level::ERROR => throughput_event!(Level::ERROR, name, rate, snapshot),
+            Level::WARN => throughput_event!(Level::WARN, name, rate, snapshot),
+            Level::INFO => throughput_event!(Level::INFO, name, rate, snapshot),
+            Level::DEBUG => throughput_event!(Level::DEBUG, name, rate, snapshot),
+            Level::TRACE => throughput_event!(Level::TRACE, name, rate, snapshot),
+        }
+
+        rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use ::tracing::field::{Field, Visit};
+    use ::tracing::{Event, Level, Subscriber};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+    use crate::testing::ManualClock;
+    use crate::tp::{RateUnit, Throughput, TimeSource};
+
+    type Fields = HashMap<String, String>;
+
+    struct FieldVisitor<'a>(&'a mut Fields);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .insert(field.name().to_owned(), format!("{:?}", value));
+        }
+    }
+
+    #[derive(Default)]
+    struct CapturingLayer {
+        events: Arc<Mutex<Vec<(Level, Fields)>>>,
+    }
+
+    impl<S: Subscriber> Layer<S> for CapturingLayer {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let mut fields = Fields::new();
+            event.record(&mut FieldVisitor(&mut fields));
+            self.events
+                .lock()
+                .unwrap()
+                .push((*event.metadata().level(), fields));
+        }
+    }
+
+    #[test]
+    fn test_log_throughput() {
+        let layer = CapturingLayer::default();
+        let events = layer.events.clone();
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        let clock = ManualClock::now();
+        let mut tp: Throughput<ManualClock> = Throughput::new();
+        tp.report(15);
+        clock.advance(Duration::from_secs(10));
+
+        let rate = ::tracing::subscriber::with_default(subscriber, || {
+            tp.log_throughput("ingest", Level::WARN)
+        });
+
+        assert_approx_eq!(rate.unwrap(), 1.5);
+        assert_eq!(tp.count(), 0);
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        let (level, fields) = &events[0];
+        assert_eq!(*level, Level::WARN);
+        assert_eq!(fields["rate"], "1.5");
+        assert_eq!(fields["sum"], "15");
+        assert_eq!(fields["elapsed_ms"], "10000");
+        // without a name of its own, the throughput is named by the target
+        assert_eq!(fields["name"], "\"ingest\"");
+    }
+
+    #[test]
+    fn test_log_throughput_unit() {
+        let layer = CapturingLayer::default();
+        let events = layer.events.clone();
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        let clock = ManualClock::now();
+        let mut tp: Throughput<ManualClock> =
+            Throughput::builder().unit(RateUnit::PerMinute).build();
+        tp.report(15);
+        clock.advance(Duration::from_secs(10));
+
+        let rate = ::tracing::subscriber::with_default(subscriber, || {
+            tp.log_throughput("ingest", Level::INFO)
+        });
+
+        assert_approx_eq!(rate.unwrap(), 90.0);
+        let events = events.lock().unwrap();
+        assert_eq!(events[0].1["rate"], "90.0");
+    }
+
+    #[test]
+    fn test_log_throughput_name() {
+        let layer = CapturingLayer::default();
+        let events = layer.events.clone();
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        let clock = ManualClock::now();
+        let mut tp: Throughput<ManualClock> = Throughput::builder().name("db").build();
+        tp.report(15);
+        clock.advance(Duration::from_secs(10));
+
+        ::tracing::subscriber::with_default(subscriber, || {
+            let _ = tp.log_throughput("ingest", Level::INFO);
+            tp.set_name("cache");
+            let _ = tp.log_throughput("ingest", Level::INFO);
+        });
+
+        let events = events.lock().unwrap();
+        assert_eq!(events[0].1["name"], "\"db\"");
+        assert_eq!(events[1].1["name"], "\"cache\"");
+    }
+}
@@ -0,0 +1,150 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::option::Option;
+
+use crate::aggregate::{Aggregator, SumPerSecond};
+use crate::tp::{Accumulate, Throughput, TimeSource};
+
+/// Marker for what the values of a [`UnitThroughput`] count.
+pub trait Unit {
+    /// Symbol of a single unit, e.g. `B` for bytes.
+    const SUFFIX: &'static str;
+}
+
+/// Values count bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bytes;
+
+impl Unit for Bytes {
+    const SUFFIX: &'static str = "B";
+}
+
+/// Values count items, e.g. messages or requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Items;
+
+impl Unit for Items {
+    const SUFFIX: &'static str = "items";
+}
+
+/// [`Throughput`] tagged with the [`Unit`] its values count.
+///
+/// The tag only exists at compile time, so e.g. a throughput of bytes
+/// cannot be passed where one of items is expected, at no runtime cost.
+/// The `Display` implementation prints the current rate with the unit's
+/// suffix, e.g. `1234.5 B/s`, or `n/a` if no time elapsed.
+pub struct UnitThroughput<
+    T: TimeSource,
+    U: Unit,
+    V: Accumulate = u64,
+    A: Aggregator<V> = SumPerSecond,
+> {
+    tp: Throughput<T, V, A>,
+    _unit: PhantomData<fn() -> U>,
+}
+
+impl<T: TimeSource, U: Unit, V: Accumulate, A: Aggregator<V>> UnitThroughput<T, U, V, A> {
+    pub fn new() -> Self {
+        Self {
+            tp: Throughput::new(),
+            _unit: PhantomData,
+        }
+    }
+
+    pub fn report(&mut self, value: V) {
+        self.tp.report(value);
+    }
+
+    /// See [`Throughput::peek_throughput`].
+    #[must_use]
+    pub fn peek_throughput(&self) -> Option<f64> {
+        self.tp.peek_throughput()
+    }
+
+    /// See [`Throughput::throughput`].
+    #[must_use = "the window is closed either way; use `reset` to discard it"]
+    pub fn throughput(&mut self) -> Option<f64> {
+        self.tp.throughput()
+    }
+
+    /// Returns the untagged throughput, e.g. for its other methods.
+    pub fn inner(&self) -> &Throughput<T, V, A> {
+        &self.tp
+    }
+
+    /// Like [`inner`](Self::inner), but for the methods which take
+    /// `&mut self`, e.g. [`Throughput::reset`].
+    pub fn inner_mut(&mut self) -> &mut Throughput<T, V, A> {
+        &mut self.tp
+    }
+}
+
+impl<T: TimeSource, U: Unit, V: Accumulate, A: Aggregator<V>> Default
+    for UnitThroughput<T, U, V, A>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: TimeSource, U: Unit, V: Accumulate + fmt::Debug, A: Aggregator<V>> fmt::Debug
+    for UnitThroughput<T, U, V, A>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnitThroughput")
+            .field("tp", &self.tp)
+            .field("unit", &U::SUFFIX)
+            .finish()
+    }
+}
+
+impl<T: TimeSource, U: Unit, V: Accumulate, A: Aggregator<V>> fmt::Display
+    for UnitThroughput<T, U, V, A>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.peek_throughput() {
+            Some(rate) => write!(f, "{:.1} {}/s", rate, U::SUFFIX),
+            None => f.write_str("n/a"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Bytes, Items, Unit, UnitThroughput};
+    use crate::testing::ManualClock;
+    use crate::tp::TimeSource;
+
+    struct Frames;
+
+    impl Unit for Frames {
+        const SUFFIX: &'static str = "frames";
+    }
+
+    #[test]
+    fn test_suffix() {
+        let clock = ManualClock::now();
+        let mut bytes: UnitThroughput<ManualClock, Bytes> = UnitThroughput::new();
+        let mut items: UnitThroughput<ManualClock, Items> = UnitThroughput::new();
+        let mut frames: UnitThroughput<ManualClock, Frames> = UnitThroughput::new();
+        assert_eq!(bytes.to_string(), "n/a");
+
+        bytes.report(2048);
+        items.report(3);
+        frames.report(60);
+        clock.advance(Duration::from_secs(2));
+
+        assert_eq!(bytes.to_string(), "1024.0 B/s");
+        assert_eq!(items.to_string(), "1.5 items/s");
+        assert_eq!(frames.to_string(), "30.0 frames/s");
+
+        assert_eq!(bytes.throughput(), Some(1024.0));
+        assert_eq!(bytes.inner().count(), 0);
+
+        bytes.report(5);
+        bytes.inner_mut().reset();
+        assert_eq!(bytes.inner().count(), 0);
+    }
+}
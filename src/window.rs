@@ -0,0 +1,257 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+use core::option::Option;
+use core::time::Duration;
+
+use crate::tp::{Accumulate, TimeSource};
+
+/// Throughput over a trailing window made of `bucket_count` buckets of
+/// `bucket_duration` each. Buckets which fall out of the window are
+/// discarded as time advances.
+#[derive(Debug)]
+pub struct WindowedThroughput<T: TimeSource, V: Accumulate = u64> {
+    start: T,
+    bucket_duration: Duration,
+    buckets: Vec<V>,
+    current_epoch: u64,
+}
+
+impl<T: TimeSource, V: Accumulate> WindowedThroughput<T, V> {
+    /// # Panics
+    ///
+    /// Panics if `bucket_count` or `bucket_duration` is zero.
+    pub fn new(bucket_count: usize, bucket_duration: Duration) -> Self {
+        assert!(bucket_count > 0, "bucket_count must be non-zero");
+        assert!(
+            !bucket_duration.is_zero(),
+            "bucket_duration must be non-zero"
+        );
+
+        Self {
+            start: T::now(),
+            bucket_duration,
+            buckets: vec![V::zero(); bucket_count],
+            current_epoch: 0,
+        }
+    }
+
+    /// Returns the approximate memory used, including the buckets.
+    pub fn approx_size_bytes(&self) -> usize {
+        mem::size_of::<Self>() + self.buckets.capacity() * mem::size_of::<V>()
+    }
+
+    pub fn report(&mut self, value: V) {
+        let epoch = self.epoch(self.start.elapsed());
+        self.advance(epoch);
+
+        let index = self.index(epoch);
+        self.buckets[index] = self.buckets[index].accumulate(value);
+    }
+
+    /// Returns the rate over the trailing window, or over the time since
+    /// construction if the window has not been filled yet.
+    #[must_use]
+    pub fn rate(&self) -> Option<f64> {
+        let elapsed = self.start.elapsed();
+        let epoch = self.epoch(elapsed);
+        let len = self.buckets.len() as u64;
+        let first_epoch = (epoch + 1).saturating_sub(len);
+
+        let sum = (first_epoch.max(self.first_valid_epoch())..=self.current_epoch)
+            .fold(V::zero(), |sum, e| {
+                sum.accumulate(self.buckets[self.index(e)])
+            });
+
+        let covered =
+            elapsed.as_nanos() - u128::from(first_epoch) * self.bucket_duration.as_nanos();
+        let denominator = covered as f64 / 1e9;
+        if denominator == 0.0 {
+            None
+        } else {
+            Some(sum.to_f64() / denominator)
+        }
+    }
+
+    fn epoch(&self, elapsed: Duration) -> u64 {
+        (elapsed.as_nanos() / self.bucket_duration.as_nanos()) as u64
+    }
+
+    fn index(&self, epoch: u64) -> usize {
+        (epoch % self.buckets.len() as u64) as usize
+    }
+
+    fn first_valid_epoch(&self) -> u64 {
+        (self.current_epoch + 1).saturating_sub(self.buckets.len() as u64)
+    }
+
+    fn advance(&mut self, epoch: u64) {
+        if epoch <= self.current_epoch {
+            return;
+        }
+
+        let stale = (epoch - self.current_epoch).min(self.buckets.len() as u64);
+        for e in (epoch + 1 - stale)..=epoch {
+            let index = self.index(e);
+            self.buckets[index] = V::zero();
+        }
+        self.current_epoch = epoch;
+    }
+}
+
+// buckets per window of a `MultiWindow`, i.e. its resolution
+const MULTI_WINDOW_BUCKETS: u32 = 10;
+
+/// Three trailing windows of different lengths fed by the same reports,
+/// like the 1, 5 and 15 minute load averages.
+///
+/// Each window is a [`WindowedThroughput`] of ten buckets, so it moves in
+/// steps of a tenth of its length. The short window reacts quickly to
+/// changes, while the long one smooths them out.
+#[derive(Debug)]
+pub struct MultiWindow<T: TimeSource, V: Accumulate = u64> {
+    windows: [WindowedThroughput<T, V>; 3],
+}
+
+impl<T: TimeSource, V: Accumulate> MultiWindow<T, V> {
+    /// Creates the windows with the given lengths, e.g. 1s, 10s and 60s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a length is shorter than ten nanoseconds.
+    pub fn new(short: Duration, medium: Duration, long: Duration) -> Self {
+        let window = |length: Duration| {
+            WindowedThroughput::new(MULTI_WINDOW_BUCKETS as usize, length / MULTI_WINDOW_BUCKETS)
+        };
+
+        Self {
+            windows: [window(short), window(medium), window(long)],
+        }
+    }
+
+    /// Reports `value` to all three windows.
+    pub fn report(&mut self, value: V) {
+        for window in &mut self.windows {
+            window.report(value);
+        }
+    }
+
+    /// Returns the rates of the short, medium and long window, see
+    /// [`WindowedThroughput::rate`].
+    #[must_use]
+    pub fn rates(&self) -> (Option<f64>, Option<f64>, Option<f64>) {
+        let [short, medium, long] = &self.windows;
+        (short.rate(), medium.rate(), long.rate())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::time::Duration;
+
+    use super::{MultiWindow, WindowedThroughput};
+    use crate::testing::ManualClock;
+    use crate::tp::TimeSource;
+
+    #[test]
+    fn test_empty() {
+        let clock = ManualClock::now();
+        let tp: WindowedThroughput<ManualClock> =
+            WindowedThroughput::new(4, Duration::from_secs(1));
+        assert_eq!(None, tp.rate());
+
+        clock.advance(Duration::from_secs(1));
+        assert_approx_eq!(tp.rate().unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_approx_size_bytes() {
+        let small: WindowedThroughput<ManualClock> =
+            WindowedThroughput::new(4, Duration::from_secs(1));
+        let large: WindowedThroughput<ManualClock> =
+            WindowedThroughput::new(64, Duration::from_secs(1));
+
+        assert_eq!(
+            large.approx_size_bytes() - small.approx_size_bytes(),
+            60 * 8
+        );
+    }
+
+    #[test]
+    fn test_partial_window() {
+        let clock = ManualClock::now();
+        let mut tp: WindowedThroughput<ManualClock> =
+            WindowedThroughput::new(4, Duration::from_secs(1));
+        tp.report(10);
+        clock.advance(Duration::from_secs(2));
+        tp.report(10);
+
+        assert_approx_eq!(tp.rate().unwrap(), 10.0);
+    }
+
+    #[test]
+    fn test_buckets_expire() {
+        let clock = ManualClock::now();
+        let mut tp: WindowedThroughput<ManualClock> =
+            WindowedThroughput::new(4, Duration::from_secs(1));
+        tp.report(100);
+
+        for _ in 0..4 {
+            clock.advance(Duration::from_secs(1));
+            tp.report(4);
+        }
+
+        // the bucket holding 100 has dropped out, the window now covers
+        // three full buckets and the (empty so far) current one
+        assert_approx_eq!(tp.rate().unwrap(), 16.0 / 3.0);
+
+        clock.advance(Duration::from_millis(1500));
+        assert_approx_eq!(tp.rate().unwrap(), 12.0 / 3.5);
+
+        clock.advance(Duration::from_secs(10));
+        assert_approx_eq!(tp.rate().unwrap(), 0.0);
+
+        tp.report(7);
+        assert_approx_eq!(tp.rate().unwrap(), 7.0 / 3.5);
+    }
+
+    #[test]
+    fn test_multi_window() {
+        let clock = ManualClock::now();
+        let mut tp: MultiWindow<ManualClock> = MultiWindow::new(
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            Duration::from_secs(60),
+        );
+        assert_eq!(tp.rates(), (None, None, None));
+
+        for _ in 0..600 {
+            tp.report(10);
+            clock.advance(Duration::from_millis(100));
+        }
+
+        let (short, medium, long) = tp.rates();
+        assert_approx_eq!(short.unwrap(), 100.0, 1.0);
+        assert_approx_eq!(medium.unwrap(), 100.0, 1.0);
+        assert_approx_eq!(long.unwrap(), 100.0, 1.0);
+
+        // after the reports stop, the short window drops first
+        clock.advance(Duration::from_secs(2));
+        let (short, medium, long) = tp.rates();
+        assert_approx_eq!(short.unwrap(), 0.0);
+        assert!(medium.unwrap() < long.unwrap());
+        assert!(long.unwrap() > 90.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_multi_window_zero_length() {
+        let _: MultiWindow<ManualClock> = MultiWindow::new(
+            Duration::default(),
+            Duration::from_secs(10),
+            Duration::from_secs(60),
+        );
+    }
+}
@@ -1,9 +1,10 @@
 // Integration tests
 
+use tp::testing::ManualClock;
 use tp::{Throughput, ThroughputSynchronized};
 
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 #[test]
 fn it_works() {
@@ -15,3 +16,18 @@ fn it_works() {
     tp.report(1);
     tp.reset();
 }
+
+#[test]
+fn manual_clock() {
+    let clock = ManualClock::new();
+    let mut tp: Throughput<ManualClock> = Throughput::starting_at(clock.clone());
+    assert_eq!(tp.peek_throughput(), None);
+
+    tp.report(30);
+    clock.advance(Duration::from_secs(3));
+    assert_eq!(tp.throughput(), Some(10.0));
+
+    tp.report(5);
+    clock.advance(Duration::from_millis(500));
+    assert_eq!(tp.throughput(), Some(10.0));
+}